@@ -0,0 +1,494 @@
+//! Pattern analysis - what a chart is made of
+//!
+//! Notes starting at the same time form a row (a chord if it has more than one
+//! note). The chart is cut into fixed-length sections, and each section is
+//! classified by how its rows follow each other: repeated lanes are jacks, chords
+//! in fast succession are jumpstreams and handstreams, single notes alternating
+//! between two lanes are trills and single notes sweeping across lanes are rolls.
+
+use std::fmt;
+
+use crate::{HitObject, ZuchsyaMap};
+
+/// Length of a classified section in ms
+pub const PATTERN_SECTION_LENGTH: f64 = 2000.0;
+
+/// Notes within this many ms of a row's first note belong to the row
+const ROW_TOLERANCE: f64 = 1.0;
+
+/// Sections with fewer rows than this are too sparse to classify
+const MIN_SECTION_ROWS: usize = 6;
+
+/// Section kinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    /// Streams of mostly two-note chords
+    Jumpstream,
+    /// Streams with three-or-more-note chords
+    Handstream,
+    /// Chords repeating lanes of the previous chord
+    Chordjack,
+    /// Single notes repeating the previous note's lane
+    Jack,
+    /// Streams with occasional repeated lanes
+    Minijack,
+    /// Single notes alternating between two lanes
+    Trill,
+    /// Single notes sweeping across lanes
+    Roll,
+    /// Mostly hold notes
+    LongNote,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Pattern::Jumpstream => "Jumpstream",
+            Pattern::Handstream => "Handstream",
+            Pattern::Chordjack => "Chordjack",
+            Pattern::Jack => "Jack",
+            Pattern::Minijack => "Minijack",
+            Pattern::Trill => "Trill",
+            Pattern::Roll => "Roll",
+            Pattern::LongNote => "LN",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Consecutive sections classified as the same pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternSection {
+    pub pattern: Pattern,
+    /// Start time in ms
+    pub start: f64,
+    /// End time in ms
+    pub end: f64,
+}
+
+impl PatternSection {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Result of a pattern analysis
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatternAnalysis {
+    /// Classified sections in time order (sparse or unclear sections are left out)
+    pub sections: Vec<PatternSection>,
+    /// Notes per second of each second of the chart, from the first note
+    pub nps: Vec<f64>,
+    /// Most notes within any one-second window
+    pub peak_nps: f64,
+    /// Number of rows of each size (index = notes in the row)
+    pub chord_sizes: Vec<usize>,
+    /// Number of notes in each lane
+    pub lane_counts: Vec<usize>,
+}
+
+impl PatternAnalysis {
+    /// Patterns by total time, most common first
+    pub fn pattern_times(&self) -> Vec<(Pattern, f64)> {
+        let mut times: Vec<(Pattern, f64)> = Vec::new();
+        for section in &self.sections {
+            match times.iter_mut().find(|(p, _)| *p == section.pattern) {
+                Some((_, time)) => *time += section.duration(),
+                None => times.push((section.pattern, section.duration())),
+            }
+        }
+        times.sort_by(|a, b| b.1.total_cmp(&a.1));
+        times
+    }
+
+    /// The pattern covering the most time
+    pub fn dominant_pattern(&self) -> Option<Pattern> {
+        self.pattern_times().first().map(|(pattern, _)| *pattern)
+    }
+
+    /// Share of each lane in the notes, in percent
+    pub fn lane_balance(&self) -> Vec<f64> {
+        let total: usize = self.lane_counts.iter().sum();
+        self.lane_counts
+            .iter()
+            .map(|&count| {
+                if total == 0 {
+                    0.0
+                } else {
+                    count as f64 * 100.0 / total as f64
+                }
+            })
+            .collect()
+    }
+
+    /// One-line summary for display
+    pub fn summary(&self) -> String {
+        let patterns = self
+            .pattern_times()
+            .iter()
+            .take(3)
+            .map(|(pattern, _)| pattern.to_string())
+            .collect::<Vec<_>>();
+        let patterns = if patterns.is_empty() {
+            "No clear patterns".to_string()
+        } else {
+            patterns.join(", ")
+        };
+
+        let rows: usize = self.chord_sizes.iter().sum();
+        let chords = self
+            .chord_sizes
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(size, count)| format!("{}: {:.0}%", size, *count as f64 * 100.0 / rows as f64))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let lanes = self
+            .lane_balance()
+            .iter()
+            .map(|share| format!("{:.0}", share))
+            .collect::<Vec<_>>()
+            .join("/");
+
+        format!(
+            "{} | Peak NPS {:.0} | Chords {} | Lanes {}%",
+            patterns, self.peak_nps, chords, lanes
+        )
+    }
+}
+
+impl ZuchsyaMap {
+    /// Analyze the patterns the chart is made of
+    pub fn analyze_patterns(&self) -> PatternAnalysis {
+        analyze_patterns(&self.hit_objects, self.difficulty.keys)
+    }
+}
+
+/// Analyze the patterns of hit objects in a chart with `keys` lanes
+pub fn analyze_patterns(hit_objects: &[HitObject], keys: u8) -> PatternAnalysis {
+    let mut objects: Vec<&HitObject> = hit_objects
+        .iter()
+        .filter(|obj| obj.lane < keys && obj.time.is_finite())
+        .collect();
+    objects.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.lane.cmp(&b.lane)));
+
+    let mut analysis = PatternAnalysis {
+        chord_sizes: vec![0; keys as usize + 1],
+        lane_counts: vec![0; keys as usize],
+        ..Default::default()
+    };
+    let (Some(first), Some(last)) = (objects.first(), objects.last()) else {
+        return analysis;
+    };
+    let (first_time, last_time) = (first.time, last.time);
+
+    for obj in &objects {
+        analysis.lane_counts[obj.lane as usize] += 1;
+    }
+
+    let rows = group_rows(&objects);
+    for row in &rows {
+        analysis.chord_sizes[row.lanes.len()] += 1;
+    }
+
+    // NPS per second, and the busiest one-second window
+    let seconds = ((last_time - first_time) / 1000.0).floor() as usize + 1;
+    analysis.nps = vec![0.0; seconds];
+    let mut window_start = 0;
+    for (i, obj) in objects.iter().enumerate() {
+        analysis.nps[((obj.time - first_time) / 1000.0) as usize] += 1.0;
+        while obj.time - objects[window_start].time >= 1000.0 {
+            window_start += 1;
+        }
+        analysis.peak_nps = analysis.peak_nps.max((i - window_start + 1) as f64);
+    }
+
+    // Classify each section, merging neighbours with the same pattern
+    let mut row_start = 0;
+    let mut section_start = first_time;
+    while section_start <= last_time {
+        let section_end = section_start + PATTERN_SECTION_LENGTH;
+        let row_end = rows[row_start..]
+            .iter()
+            .position(|row| row.time >= section_end)
+            .map_or(rows.len(), |n| row_start + n);
+
+        if let Some(pattern) = classify(&rows[row_start..row_end]) {
+            match analysis.sections.last_mut() {
+                Some(section) if section.pattern == pattern && section.end == section_start => {
+                    section.end = section_end;
+                }
+                _ => analysis.sections.push(PatternSection {
+                    pattern,
+                    start: section_start,
+                    end: section_end,
+                }),
+            }
+        }
+
+        row_start = row_end;
+        section_start = section_end;
+    }
+
+    analysis
+}
+
+/// Notes starting together
+struct Row {
+    time: f64,
+    /// Lanes of the notes, ascending
+    lanes: Vec<u8>,
+    holds: usize,
+}
+
+impl Row {
+    fn shares_lane(&self, other: &Row) -> bool {
+        self.lanes.iter().any(|lane| other.lanes.contains(lane))
+    }
+}
+
+/// Group time-sorted objects into rows
+fn group_rows(objects: &[&HitObject]) -> Vec<Row> {
+    let mut rows: Vec<Row> = Vec::new();
+    for obj in objects {
+        match rows.last_mut() {
+            Some(row) if obj.time - row.time <= ROW_TOLERANCE => {
+                if !row.lanes.contains(&obj.lane) {
+                    row.lanes.push(obj.lane);
+                }
+                row.holds += usize::from(obj.is_hold());
+            }
+            _ => rows.push(Row {
+                time: obj.time,
+                lanes: vec![obj.lane],
+                holds: usize::from(obj.is_hold()),
+            }),
+        }
+    }
+    rows
+}
+
+/// Classify a section's rows, None if it has no clear pattern
+fn classify(rows: &[Row]) -> Option<Pattern> {
+    if rows.len() < MIN_SECTION_ROWS {
+        return None;
+    }
+
+    let notes: usize = rows.iter().map(|row| row.lanes.len()).sum();
+    let holds: usize = rows.iter().map(|row| row.holds).sum();
+    if holds * 2 >= notes {
+        return Some(Pattern::LongNote);
+    }
+
+    let transitions = (rows.len() - 1) as f64;
+    let ratio = |count: usize| count as f64 / transitions;
+    let rows_ratio = |count: usize| count as f64 / rows.len() as f64;
+
+    let jacks = rows
+        .windows(2)
+        .filter(|pair| pair[1].shares_lane(&pair[0]))
+        .count();
+    let jumps = rows.iter().filter(|row| row.lanes.len() == 2).count();
+    let hands = rows.iter().filter(|row| row.lanes.len() >= 3).count();
+
+    if ratio(jacks) >= 0.5 {
+        return Some(if rows_ratio(jumps + hands) >= 0.5 {
+            Pattern::Chordjack
+        } else {
+            Pattern::Jack
+        });
+    }
+    if rows_ratio(hands) >= 0.15 {
+        return Some(Pattern::Handstream);
+    }
+    if rows_ratio(jumps) >= 0.25 {
+        return Some(Pattern::Jumpstream);
+    }
+    if ratio(jacks) >= 0.15 {
+        return Some(Pattern::Minijack);
+    }
+
+    // Single-note patterns, judged on lane movement
+    let lanes: Vec<i16> = rows.iter().map(|row| row.lanes[0] as i16).collect();
+    let trills = lanes
+        .windows(3)
+        .filter(|w| w[0] == w[2] && w[0] != w[1])
+        .count();
+    let rolls = lanes
+        .windows(3)
+        .filter(|w| {
+            let (a, b) = (w[1] - w[0], w[2] - w[1]);
+            a != 0 && a.signum() == b.signum()
+        })
+        .count();
+    let triples = lanes.len().saturating_sub(2).max(1) as f64;
+
+    if trills as f64 / triples >= 0.6 {
+        Some(Pattern::Trill)
+    } else if rolls as f64 / triples >= 0.5 {
+        // Rolls wrap around at the edges (0123 0123), so half the steps are enough
+        Some(Pattern::Roll)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows of notes `spacing` ms apart, cycling through `cycle` until `count` rows
+    fn chart(cycle: &[&[u8]], spacing: f64, count: usize) -> Vec<HitObject> {
+        (0..count)
+            .flat_map(|i| {
+                let time = i as f64 * spacing;
+                cycle[i % cycle.len()]
+                    .iter()
+                    .map(move |&lane| HitObject::note(lane, time))
+            })
+            .collect()
+    }
+
+    /// Patterns of the sections of a chart
+    fn patterns(objects: &[HitObject]) -> Vec<Pattern> {
+        analyze_patterns(objects, 4)
+            .sections
+            .iter()
+            .map(|section| section.pattern)
+            .collect()
+    }
+
+    #[test]
+    fn jumpstream() {
+        let objects = chart(&[&[0, 1], &[2], &[0, 3], &[1]], 125.0, 16);
+        assert_eq!(patterns(&objects), [Pattern::Jumpstream]);
+    }
+
+    #[test]
+    fn handstream() {
+        let objects = chart(&[&[0, 1, 2], &[3], &[0, 2], &[1]], 125.0, 16);
+        assert_eq!(patterns(&objects), [Pattern::Handstream]);
+    }
+
+    #[test]
+    fn chordjack() {
+        let objects = chart(&[&[0, 1, 2], &[1, 2, 3]], 150.0, 12);
+        assert_eq!(patterns(&objects), [Pattern::Chordjack]);
+    }
+
+    #[test]
+    fn jack() {
+        let objects = chart(&[&[0]], 150.0, 12);
+        assert_eq!(patterns(&objects), [Pattern::Jack]);
+    }
+
+    #[test]
+    fn trill() {
+        let objects = chart(&[&[1], &[2]], 100.0, 20);
+        assert_eq!(patterns(&objects), [Pattern::Trill]);
+    }
+
+    #[test]
+    fn roll() {
+        let objects = chart(&[&[0], &[1], &[2], &[3]], 100.0, 20);
+        assert_eq!(patterns(&objects), [Pattern::Roll]);
+    }
+
+    #[test]
+    fn long_notes() {
+        let objects: Vec<HitObject> = (0..8)
+            .map(|i| {
+                let time = i as f64 * 250.0;
+                if i % 2 == 0 {
+                    HitObject::hold(i % 4, time, 200.0)
+                } else {
+                    HitObject::note(i % 4, time)
+                }
+            })
+            .collect();
+        assert_eq!(patterns(&objects), [Pattern::LongNote]);
+    }
+
+    #[test]
+    fn sections_merge_and_sparse_ones_are_skipped() {
+        // 4s of trills, 2s with only three notes, then 2s of jacks
+        let mut objects = chart(&[&[0], &[3]], 100.0, 40);
+        objects.extend([500.0, 1000.0, 1500.0].map(|t| HitObject::note(1, 4000.0 + t)));
+        objects.extend(
+            chart(&[&[2]], 150.0, 12)
+                .into_iter()
+                .map(|obj| HitObject::note(obj.lane, obj.time + 6000.0)),
+        );
+
+        let analysis = analyze_patterns(&objects, 4);
+        assert_eq!(
+            analysis.sections,
+            [
+                PatternSection {
+                    pattern: Pattern::Trill,
+                    start: 0.0,
+                    end: 4000.0,
+                },
+                PatternSection {
+                    pattern: Pattern::Jack,
+                    start: 6000.0,
+                    end: 8000.0,
+                },
+            ]
+        );
+        assert_eq!(analysis.dominant_pattern(), Some(Pattern::Trill));
+    }
+
+    #[test]
+    fn nps() {
+        let objects = [
+            HitObject::note(0, 1000.0),
+            HitObject::note(1, 1100.0),
+            HitObject::note(2, 1100.0),
+            HitObject::note(3, 1900.0),
+            HitObject::note(0, 2500.0),
+            HitObject::note(1, 3999.0),
+        ];
+        let analysis = analyze_patterns(&objects, 4);
+        // Seconds count from the first note
+        assert_eq!(analysis.nps, [4.0, 1.0, 1.0]);
+        // The busiest window, 1000-1999ms
+        assert_eq!(analysis.peak_nps, 4.0);
+    }
+
+    #[test]
+    fn peak_nps_window_slides() {
+        // Five notes within 1000ms that straddle two of the per-second buckets
+        let objects: Vec<HitObject> = (0..5)
+            .map(|i| HitObject::note(i % 4, 600.0 + i as f64 * 200.0))
+            .chain([HitObject::note(0, 0.0)])
+            .collect();
+        let analysis = analyze_patterns(&objects, 4);
+        assert_eq!(analysis.nps, [3.0, 3.0]);
+        assert_eq!(analysis.peak_nps, 5.0);
+    }
+
+    #[test]
+    fn chords_and_lane_balance() {
+        let objects = chart(&[&[0], &[0, 1], &[0, 1, 2], &[3]], 200.0, 4);
+        let analysis = analyze_patterns(&objects, 4);
+        assert_eq!(analysis.chord_sizes, [0, 2, 1, 1, 0]);
+        assert_eq!(analysis.lane_counts, [3, 2, 1, 1]);
+
+        let balance = analysis.lane_balance();
+        let expected = [3.0, 2.0, 1.0, 1.0].map(|count| count * 100.0 / 7.0);
+        assert_eq!(balance.len(), expected.len());
+        for (share, expected) in balance.iter().zip(expected) {
+            assert!((share - expected).abs() < 1e-9, "{balance:?}");
+        }
+    }
+
+    #[test]
+    fn empty_chart() {
+        let analysis = analyze_patterns(&[], 4);
+        assert!(analysis.sections.is_empty());
+        assert_eq!(analysis.peak_nps, 0.0);
+        assert_eq!(analysis.lane_balance(), [0.0; 4]);
+    }
+}
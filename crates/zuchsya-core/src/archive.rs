@@ -0,0 +1,229 @@
+//! Beatmap set archives (.zsz)
+//!
+//! A set archive is a zip file with the set's .zuchsya difficulties and every asset
+//! they reference (audio, background), stored at the paths the maps use. Importing
+//! extracts it into its own folder inside the beatmaps folder.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{BeatmapError, ZuchsyaMap};
+
+/// File extension of beatmap set archives
+pub const SET_ARCHIVE_EXTENSION: &str = "zsz";
+
+/// File extension of beatmaps
+const BEATMAP_EXTENSION: &str = "zuchsya";
+
+/// Difficulties of a beatmap set, by file name
+pub type SetBeatmaps = Vec<(String, ZuchsyaMap)>;
+
+/// Export every .zuchsya file in `folder` and the assets they reference
+pub fn export_set(folder: &Path, archive_path: &Path) -> Result<(), ArchiveError> {
+    let maps = load_set_folder(folder)?;
+
+    let mut files: Vec<String> = Vec::new();
+    for (file, map) in &maps {
+        files.push(file.clone());
+        for asset in map.referenced_assets() {
+            check_safe_path(asset)?;
+            if !folder.join(asset).is_file() {
+                return Err(ArchiveError::MissingAsset {
+                    map: file.clone(),
+                    asset: asset.to_string(),
+                });
+            }
+            if !files.iter().any(|f| f == asset) {
+                files.push(asset.to_string());
+            }
+        }
+    }
+
+    if let Some(parent) = archive_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut writer = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in &files {
+        writer.start_file(file.replace('\\', "/"), options)?;
+        std::io::copy(&mut File::open(folder.join(file))?, &mut writer)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Check that an archive is a valid beatmap set, returning its difficulties
+pub fn validate_set_archive(archive_path: &Path) -> Result<SetBeatmaps, ArchiveError> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let (maps, _) = read_set_archive(&mut archive)?;
+    Ok(maps)
+}
+
+/// Validate and extract an archive into a new folder in `beatmaps_dir`
+///
+/// Returns the folder the set was extracted to.
+pub fn import_set(archive_path: &Path, beatmaps_dir: &Path) -> Result<PathBuf, ArchiveError> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let (maps, entries) = read_set_archive(&mut archive)?;
+
+    let name = maps
+        .first()
+        .map(|(_, map)| format!("{} - {}", map.metadata.artist, map.metadata.title))
+        .filter(|name| name != " - ")
+        .or_else(|| {
+            archive_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "Imported set".to_string());
+    let folder = unique_folder(beatmaps_dir, &sanitize_folder_name(&name));
+    std::fs::create_dir_all(&folder)?;
+
+    for (index, path) in entries {
+        let mut entry = archive.by_index(index)?;
+        let out_path = folder.join(path);
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::io::copy(&mut entry, &mut File::create(&out_path)?)?;
+    }
+
+    Ok(folder)
+}
+
+/// Load every .zuchsya file in a set folder
+fn load_set_folder(folder: &Path) -> Result<SetBeatmaps, ArchiveError> {
+    let mut maps = Vec::new();
+    for entry in std::fs::read_dir(folder)?.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == BEATMAP_EXTENSION) {
+            let file = entry.file_name().to_string_lossy().into_owned();
+            let map = ZuchsyaMap::load(&path).map_err(|source| ArchiveError::Beatmap {
+                file: file.clone(),
+                source,
+            })?;
+            maps.push((file, map));
+        }
+    }
+    maps.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if maps.is_empty() {
+        return Err(ArchiveError::NoBeatmaps);
+    }
+    Ok(maps)
+}
+
+/// Parse the difficulties of an archive and check their assets are in it
+///
+/// Also returns the (index, path) of every file entry, for extraction.
+fn read_set_archive(
+    archive: &mut ZipArchive<File>,
+) -> Result<(SetBeatmaps, Vec<(usize, PathBuf)>), ArchiveError> {
+    let mut maps = Vec::new();
+    let mut entries = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(path) = entry.enclosed_name() else {
+            return Err(ArchiveError::UnsafePath(entry.name().to_string()));
+        };
+
+        if path.extension().is_some_and(|ext| ext == BEATMAP_EXTENSION) {
+            let file = path.to_string_lossy().into_owned();
+            let mut yaml = String::new();
+            entry.read_to_string(&mut yaml)?;
+            let map = ZuchsyaMap::from_yaml(&yaml).map_err(|source| ArchiveError::Beatmap {
+                file: file.clone(),
+                source,
+            })?;
+            maps.push((file, map));
+        }
+        entries.push((index, path));
+    }
+
+    if maps.is_empty() {
+        return Err(ArchiveError::NoBeatmaps);
+    }
+
+    let files: HashSet<&Path> = entries.iter().map(|(_, path)| path.as_path()).collect();
+    for (file, map) in &maps {
+        for asset in map.referenced_assets() {
+            check_safe_path(asset)?;
+            if !files.contains(Path::new(asset)) {
+                return Err(ArchiveError::MissingAsset {
+                    map: file.clone(),
+                    asset: asset.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok((maps, entries))
+}
+
+/// Asset references must stay inside the set folder
+fn check_safe_path(asset: &str) -> Result<(), ArchiveError> {
+    let safe = Path::new(asset)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if safe {
+        Ok(())
+    } else {
+        Err(ArchiveError::UnsafePath(asset.to_string()))
+    }
+}
+
+/// Replace characters that aren't allowed in folder names
+fn sanitize_folder_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    sanitized.trim().trim_end_matches('.').to_string()
+}
+
+/// `dir/name`, or `dir/name (2)`, `dir/name (3)`... if it already exists
+fn unique_folder(dir: &Path, name: &str) -> PathBuf {
+    let mut folder = dir.join(name);
+    let mut n = 2;
+    while folder.exists() {
+        folder = dir.join(format!("{} ({})", name, n));
+        n += 1;
+    }
+    folder
+}
+
+/// Beatmap set archive errors
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("{file}: {source}")]
+    Beatmap {
+        file: String,
+        #[source]
+        source: BeatmapError,
+    },
+    #[error("{map} references {asset}, which is missing")]
+    MissingAsset { map: String, asset: String },
+    #[error("No .zuchsya beatmaps in the set")]
+    NoBeatmaps,
+    #[error("Unsafe path: {0}")]
+    UnsafePath(String),
+}
@@ -3,7 +3,7 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-use crate::{HitObject, ScrollVelocity, TimingPoint};
+use crate::{Diagnostic, HitObject, ScrollVelocity, TimingPoint, migration};
 
 /// Current format version
 pub const FORMAT_VERSION: u32 = 1;
@@ -57,6 +57,9 @@ pub struct Metadata {
     /// Tags for searching
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
+    /// ID shared by all difficulties of the set (optional, see `ZuchsyaMap::set_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_id: Option<String>,
 }
 
 /// Audio configuration
@@ -85,7 +88,7 @@ impl Default for AudioInfo {
 /// Difficulty settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Difficulty {
-    /// Number of keys (4-8)
+    /// Number of keys (1-10)
     pub keys: u8,
     /// Overall Difficulty (0-10, affects hit windows)
     pub od: f32,
@@ -149,11 +152,45 @@ impl ZuchsyaMap {
         Self::from_yaml(&content)
     }
 
-    /// Parse beatmap from YAML string
+    /// Parse beatmap from YAML string, upgrading older format versions
     pub fn from_yaml(yaml: &str) -> Result<Self, BeatmapError> {
-        let map: Self = serde_yaml::from_str(yaml)?;
+        Ok(Self::from_yaml_migrated(yaml)?.0)
+    }
+
+    /// Parse beatmap from YAML string, also returning the format version it was written in
+    pub fn from_yaml_migrated(yaml: &str) -> Result<(Self, u32), BeatmapError> {
+        let (map, original_version) = Self::parse_migrated(yaml)?;
         map.validate()?;
-        Ok(map)
+        Ok((map, original_version))
+    }
+
+    /// Load beatmap from YAML file without validating it
+    ///
+    /// For tools that report or fix problems, see `diagnostics`.
+    pub fn load_unvalidated(path: &Path) -> Result<Self, BeatmapError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse_migrated(&content)?.0)
+    }
+
+    fn parse_migrated(yaml: &str) -> Result<(Self, u32), BeatmapError> {
+        let document: serde_yaml::Value = serde_yaml::from_str(yaml)?;
+        let (document, original_version) = migration::migrate(document)?;
+        let map: Self = serde_yaml::from_value(document)?;
+        Ok((map, original_version))
+    }
+
+    /// Rewrite a beatmap file in the current format version
+    ///
+    /// Returns false if the file already was current and was left untouched.
+    /// Comments in upgraded files are not preserved.
+    pub fn resave(path: &Path) -> Result<bool, BeatmapError> {
+        let content = std::fs::read_to_string(path)?;
+        let (map, original_version) = Self::from_yaml_migrated(&content)?;
+        if original_version == FORMAT_VERSION {
+            return Ok(false);
+        }
+        map.save(path)?;
+        Ok(true)
     }
 
     /// Serialize beatmap to YAML string
@@ -168,19 +205,18 @@ impl ZuchsyaMap {
         Ok(())
     }
 
-    /// Validate beatmap data
+    /// Validate beatmap data, failing on every error-severity diagnostic
     pub fn validate(&self) -> Result<(), BeatmapError> {
-        if self.timing.is_empty() {
-            return Err(BeatmapError::Validation(
-                "At least one timing point is required".into(),
-            ));
-        }
-        if self.difficulty.keys < 1 || self.difficulty.keys > 10 {
-            return Err(BeatmapError::Validation(
-                "Key count must be between 1 and 10".into(),
-            ));
+        let errors: Vec<Diagnostic> = self
+            .diagnostics()
+            .into_iter()
+            .filter(Diagnostic::is_error)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(BeatmapError::Invalid(errors))
         }
-        Ok(())
     }
 
     /// Get total duration in milliseconds
@@ -206,6 +242,18 @@ impl ZuchsyaMap {
         self.hit_objects.iter().filter(|o| o.is_hold()).count()
     }
 
+    /// Files the beatmap references (audio and background), relative to its folder
+    pub fn referenced_assets(&self) -> Vec<&str> {
+        let mut assets = Vec::new();
+        if !self.audio.file.is_empty() {
+            assets.push(self.audio.file.as_str());
+        }
+        if let Some(background) = &self.background {
+            assets.push(background.as_str());
+        }
+        assets
+    }
+
     /// Get BPM at time 0 (or first timing point)
     pub fn bpm(&self) -> f64 {
         self.timing.first().map(|t| t.bpm).unwrap_or(120.0)
@@ -219,6 +267,22 @@ pub enum BeatmapError {
     Io(#[from] std::io::Error),
     #[error("YAML parse error: {0}")]
     Yaml(#[from] serde_yaml::Error),
-    #[error("Validation error: {0}")]
-    Validation(String),
+    #[error("Missing format version")]
+    MissingVersion,
+    #[error("Invalid format version {0}")]
+    InvalidVersion(String),
+    #[error("Beatmap format version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("Beatmap must be a YAML mapping")]
+    NotAMapping,
+    #[error("Invalid beatmap: {}", join_diagnostics(.0))]
+    Invalid(Vec<Diagnostic>),
+}
+
+fn join_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(Diagnostic::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+//! Beat-based timing shared by the measure/beat based formats (StepMania, BMS)
+
+use crate::TimingPoint;
+
+/// Tempo map converting beats to milliseconds
+pub(super) struct BeatTiming {
+    /// Time of beat 0 in ms
+    pub offset_ms: f64,
+    /// (beat, bpm), sorted by beat
+    pub bpms: Vec<(f64, f64)>,
+    /// (beat, ms) pauses after the notes on the beat
+    pub stops: Vec<(f64, f64)>,
+    /// (beat, ms) pauses before the notes on the beat
+    pub delays: Vec<(f64, f64)>,
+    /// (beat, beats per measure) where the measure length changes, sorted by beat
+    pub measures: Vec<(f64, u8)>,
+}
+
+impl BeatTiming {
+    /// BPM in effect at a beat
+    pub(super) fn bpm_at(&self, beat: f64) -> f64 {
+        self.bpms
+            .iter()
+            .take_while(|(b, _)| *b <= beat)
+            .last()
+            .unwrap_or(&self.bpms[0])
+            .1
+    }
+
+    /// Beats per measure at a beat
+    pub(super) fn signature_at(&self, beat: f64) -> u8 {
+        self.measures
+            .iter()
+            .take_while(|(b, _)| *b <= beat)
+            .last()
+            .map_or(4, |(_, signature)| *signature)
+    }
+
+    /// Time of the notes on a beat in ms
+    pub(super) fn beat_time(&self, beat: f64) -> f64 {
+        // Beats before the first BPM change use the first BPM
+        let mut bpm = self.bpms[0].1;
+        let mut prev_beat = 0.0_f64.min(beat);
+        let mut ms = if beat < 0.0 {
+            beat * 60000.0 / bpm
+        } else {
+            0.0
+        };
+
+        for &(change_beat, change_bpm) in &self.bpms {
+            if change_beat >= beat {
+                break;
+            }
+            if change_beat > prev_beat {
+                ms += (change_beat - prev_beat) * 60000.0 / bpm;
+                prev_beat = change_beat;
+            }
+            bpm = change_bpm;
+        }
+        if beat > prev_beat {
+            ms += (beat - prev_beat) * 60000.0 / bpm;
+        }
+
+        let stops: f64 = self
+            .stops
+            .iter()
+            .filter(|(b, _)| *b < beat)
+            .map(|(_, ms)| ms)
+            .sum();
+        let delays: f64 = self
+            .delays
+            .iter()
+            .filter(|(b, _)| *b <= beat)
+            .map(|(_, ms)| ms)
+            .sum();
+
+        self.offset_ms + ms + stops + delays
+    }
+
+    /// Timing points for every BPM and measure length change, restarting the beat grid
+    /// after each pause
+    pub(super) fn timing_points(&self) -> Vec<TimingPoint> {
+        let mut beats: Vec<f64> = self
+            .bpms
+            .iter()
+            .chain(&self.stops)
+            .chain(&self.delays)
+            .map(|(beat, _)| *beat)
+            .chain(self.measures.iter().map(|(beat, _)| *beat))
+            .collect();
+        beats.sort_by(f64::total_cmp);
+        beats.dedup();
+
+        let mut points: Vec<TimingPoint> = Vec::new();
+        for beat in beats {
+            let bpm = self.bpm_at(beat);
+            let stop: f64 = self
+                .stops
+                .iter()
+                .filter(|(b, _)| *b == beat)
+                .map(|(_, ms)| ms)
+                .sum();
+            let paused = stop > 0.0 || self.delays.iter().any(|(b, ms)| *b == beat && *ms > 0.0);
+
+            let measure_starts = self.measures.iter().any(|(b, _)| *b == beat);
+
+            if !paused && !measure_starts && points.last().is_some_and(|last| last.bpm == bpm) {
+                continue;
+            }
+            points.push(TimingPoint::with_signature(
+                self.beat_time(beat) + stop,
+                bpm,
+                self.signature_at(beat),
+            ));
+        }
+
+        points
+    }
+}
@@ -0,0 +1,713 @@
+//! BMS family (.bms, .bme, .bml, .pms) import
+//!
+//! - 1P channels 11-19 (visible) and 51-59 (long notes) become lanes; 5/7 keys get an
+//!   extra leftmost lane when the scratch (channel 16) is used, so 7+1 maps to 8 keys
+//! - PMS uses 1P 11-15 and 2P 22-25 for its 9 buttons
+//! - Keysounds are dropped; the first background sound becomes the song audio
+//!
+//! Anything that can't be represented is reported as a diagnostic instead of failing.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use super::beats::BeatTiming;
+use super::{ConvertError, ImportDiagnostic, Imported, parse_num};
+use crate::{HitObject, ZuchsyaMap};
+
+/// BPM when the file doesn't set one
+const DEFAULT_BPM: f64 = 130.0;
+
+/// `#STOPxx` values are in 1/192 of a 4/4 measure
+const STOP_UNITS_PER_BEAT: f64 = 48.0;
+
+/// Button layout of a BMS family file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BmsKind {
+    /// Beatmania style 5/7 keys with optional scratch (.bms, .bme, .bml)
+    Beat,
+    /// Pop'n style 9 buttons (.pms)
+    PopN,
+}
+
+impl BmsKind {
+    /// Layout for a file extension
+    pub fn from_path(path: &Path) -> Self {
+        let is_pms = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pms"));
+        if is_pms { Self::PopN } else { Self::Beat }
+    }
+}
+
+impl ZuchsyaMap {
+    /// Load and convert a BMS family file
+    pub fn load_bms(path: &Path) -> Result<Imported, ConvertError> {
+        let bytes = std::fs::read(path)?;
+
+        // Most BMS files are Shift-JIS, which is read lossily
+        let (content, lossy) = match String::from_utf8(bytes) {
+            Ok(content) => (content, false),
+            Err(err) => (String::from_utf8_lossy(err.as_bytes()).into_owned(), true),
+        };
+
+        let mut imported = Self::from_bms(&content, BmsKind::from_path(path))?;
+        if lossy {
+            imported.diagnostics.insert(
+                0,
+                ImportDiagnostic::new(
+                    None,
+                    "File is not UTF-8 (probably Shift-JIS), non-ASCII text may be garbled",
+                ),
+            );
+        }
+        Ok(imported)
+    }
+
+    /// Convert BMS family file contents
+    pub fn from_bms(content: &str, kind: BmsKind) -> Result<Imported, ConvertError> {
+        let mut bms = BmsFile::parse(content)?;
+        let map = bms.convert(kind)?;
+        Ok(Imported {
+            map,
+            diagnostics: bms.diagnostics,
+        })
+    }
+}
+
+/// Object in a channel line: `#mmmcc:` data split into two-character ids
+struct BmsObject {
+    line: usize,
+    measure: usize,
+    /// Position inside the measure (0.0 - 1.0)
+    position: f64,
+    channel: String,
+    id: String,
+}
+
+#[derive(Default)]
+struct BmsFile {
+    /// Header commands: key -> (line, value)
+    headers: HashMap<String, (usize, String)>,
+    wavs: HashMap<String, String>,
+    bpms: HashMap<String, f64>,
+    stops: HashMap<String, f64>,
+    /// Measure length multipliers (channel 02)
+    measure_lengths: BTreeMap<usize, f64>,
+    objects: Vec<BmsObject>,
+    diagnostics: Vec<ImportDiagnostic>,
+}
+
+/// `#IF` block state
+struct Branch {
+    parent_active: bool,
+    taken: bool,
+}
+
+impl BmsFile {
+    fn parse(content: &str) -> Result<Self, ConvertError> {
+        let mut bms = BmsFile::default();
+        let mut branches: Vec<Branch> = Vec::new();
+        let mut active = true;
+        let mut warned_random = false;
+
+        for (index, raw) in content.lines().enumerate() {
+            let line = index + 1;
+            let text = raw.trim();
+            let Some(command) = text.strip_prefix('#') else {
+                continue;
+            };
+
+            let (key, value) = match command.split_once(char::is_whitespace) {
+                Some((key, value)) => (key.to_ascii_uppercase(), value.trim()),
+                None => (command.to_ascii_uppercase(), ""),
+            };
+
+            // Random branches: always take the #IF 1 branch
+            match key.as_str() {
+                "RANDOM" | "SETRANDOM" => {
+                    if !warned_random {
+                        bms.diagnostics.push(ImportDiagnostic::new(
+                            Some(line),
+                            "#RANDOM charts are converted using branch 1",
+                        ));
+                        warned_random = true;
+                    }
+                    continue;
+                }
+                "IF" => {
+                    let taken = value == "1";
+                    branches.push(Branch {
+                        parent_active: active,
+                        taken,
+                    });
+                    active = active && taken;
+                    continue;
+                }
+                "ELSEIF" | "ELSE" => {
+                    if let Some(branch) = branches.last_mut() {
+                        let take = !branch.taken && (key == "ELSE" || value == "1");
+                        branch.taken |= take;
+                        active = branch.parent_active && take;
+                    }
+                    continue;
+                }
+                "ENDIF" | "END" => {
+                    if let Some(branch) = branches.pop() {
+                        active = branch.parent_active;
+                    }
+                    continue;
+                }
+                "ENDRANDOM" => continue,
+                _ => {}
+            }
+            if !active {
+                continue;
+            }
+
+            if let Some((measure, channel, data)) = split_channel_line(command) {
+                bms.parse_channel(line, measure, channel, data)?;
+            } else if let Some(id) = key.strip_prefix("WAV") {
+                bms.wavs.insert(id.to_string(), value.to_string());
+            } else if let Some(id) = key.strip_prefix("BPM").filter(|id| !id.is_empty()) {
+                bms.bpms
+                    .insert(id.to_string(), parse_num(line, &key, value)?);
+            } else if let Some(id) = key.strip_prefix("STOP").filter(|id| !id.is_empty()) {
+                bms.stops
+                    .insert(id.to_string(), parse_num(line, &key, value)?);
+            } else {
+                bms.headers.insert(key, (line, value.to_string()));
+            }
+        }
+
+        Ok(bms)
+    }
+
+    fn parse_channel(
+        &mut self,
+        line: usize,
+        measure: usize,
+        channel: String,
+        data: &str,
+    ) -> Result<(), ConvertError> {
+        if channel == "02" {
+            let length: f64 = parse_num(line, "measure length", data)?;
+            if length <= 0.0 {
+                return Err(ConvertError::parse(line, "Measure length must be positive"));
+            }
+            self.measure_lengths.insert(measure, length);
+            return Ok(());
+        }
+
+        let data: Vec<char> = data.chars().filter(|c| !c.is_whitespace()).collect();
+        if !data.len().is_multiple_of(2) {
+            return Err(ConvertError::parse(
+                line,
+                "Channel data must be made of two-character ids",
+            ));
+        }
+
+        let count = data.len() / 2;
+        for (i, pair) in data.chunks(2).enumerate() {
+            let id: String = pair.iter().collect::<String>().to_ascii_uppercase();
+            if id == "00" {
+                continue;
+            }
+            self.objects.push(BmsObject {
+                line,
+                measure,
+                position: i as f64 / count as f64,
+                channel: channel.clone(),
+                id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Beat at the start of each measure up to `last`
+    fn measure_starts(&self, last: usize) -> Vec<f64> {
+        let mut starts = Vec::with_capacity(last + 2);
+        let mut beat = 0.0;
+        for measure in 0..=last + 1 {
+            starts.push(beat);
+            beat += self.measure_beats(measure);
+        }
+        starts
+    }
+
+    fn measure_beats(&self, measure: usize) -> f64 {
+        4.0 * self.measure_lengths.get(&measure).copied().unwrap_or(1.0)
+    }
+
+    fn convert(&mut self, kind: BmsKind) -> Result<ZuchsyaMap, ConvertError> {
+        let last_measure = self
+            .objects
+            .iter()
+            .map(|obj| obj.measure)
+            .chain(self.measure_lengths.keys().copied())
+            .max()
+            .unwrap_or(0);
+        let starts = self.measure_starts(last_measure);
+        let mut timing = self.build_timing(&starts, last_measure)?;
+
+        let beat_of =
+            |obj: &BmsObject| starts[obj.measure] + self.measure_beats(obj.measure) * obj.position;
+        let mut diagnostics = Vec::new();
+
+        // Use the first background sound as the song audio, starting at 0 ms
+        let mut bgm: Vec<&BmsObject> = self.objects.iter().filter(|o| o.channel == "01").collect();
+        bgm.sort_by(|a, b| beat_of(a).total_cmp(&beat_of(b)));
+        let mut audio_file = String::new();
+        let mut audio_id = None;
+        if let Some(first) = bgm.first() {
+            match self.wavs.get(&first.id) {
+                Some(file) => {
+                    timing.offset_ms = -timing.beat_time(beat_of(first));
+                    audio_file = file.clone();
+                    audio_id = Some(&first.id);
+                    diagnostics.push(ImportDiagnostic::new(
+                        Some(first.line),
+                        format!("Using {} as the song audio", file),
+                    ));
+                }
+                None => diagnostics.push(ImportDiagnostic::new(
+                    Some(first.line),
+                    format!("Background sound {} has no #WAV definition", first.id),
+                )),
+            }
+        }
+
+        // Lane for each playable channel
+        let lane_of = self.lane_layout(kind);
+        let keys = lane_of.values().copied().max().map_or(0, |lane| lane + 1);
+
+        let lnobj = self
+            .headers
+            .get("LNOBJ")
+            .map(|(_, id)| id.to_ascii_uppercase());
+        let mut columns: BTreeMap<u8, Vec<(f64, &BmsObject)>> = BTreeMap::new();
+        let mut ln_columns: BTreeMap<u8, Vec<(f64, &BmsObject)>> = BTreeMap::new();
+        let mut ignored: BTreeMap<&'static str, usize> = BTreeMap::new();
+
+        for obj in &self.objects {
+            let (is_ln, digit) = match obj.channel.as_bytes() {
+                [b'1', d] => (false, *d),
+                [b'5', d] => (true, *d),
+                [b'2', d] if kind == BmsKind::PopN => (false, *d + 10),
+                [b'6', d] if kind == BmsKind::PopN => (true, *d + 10),
+                [b'2' | b'6', _] => {
+                    *ignored.entry("2P notes").or_default() += 1;
+                    continue;
+                }
+                [b'3' | b'4', _] => {
+                    *ignored.entry("invisible notes").or_default() += 1;
+                    continue;
+                }
+                [b'D' | b'E', _] => {
+                    *ignored.entry("landmines").or_default() += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let Some(&lane) = lane_of.get(&digit) else {
+                *ignored.entry("notes in unused channels").or_default() += 1;
+                continue;
+            };
+            let target = if is_ln { &mut ln_columns } else { &mut columns };
+            target.entry(lane).or_default().push((beat_of(obj), obj));
+        }
+
+        let mut hit_objects = Vec::new();
+
+        // Visible notes, with #LNOBJ ending the previous note in the column
+        for (&lane, notes) in columns.iter_mut() {
+            notes.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut column: Vec<HitObject> = Vec::new();
+            for &(beat, obj) in notes.iter() {
+                let time = timing.beat_time(beat);
+                if lnobj.as_ref() == Some(&obj.id) {
+                    match column.last_mut() {
+                        Some(prev) if !prev.is_hold() && time > prev.time => {
+                            prev.duration = Some(time - prev.time)
+                        }
+                        Some(prev) if !prev.is_hold() => diagnostics.push(ImportDiagnostic::new(
+                            Some(obj.line),
+                            "#LNOBJ end on the same beat as its start, converted to a tap note",
+                        )),
+                        _ => diagnostics.push(ImportDiagnostic::new(
+                            Some(obj.line),
+                            "#LNOBJ end without a note to start the long note",
+                        )),
+                    }
+                    continue;
+                }
+                column.push(HitObject::note(lane, time));
+            }
+            hit_objects.extend(column);
+        }
+
+        // Long note channels: objects alternate between start and end
+        for (&lane, notes) in ln_columns.iter_mut() {
+            notes.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for pair in notes.chunks(2) {
+                let start = timing.beat_time(pair[0].0);
+                let end = pair.get(1).map(|&(end_beat, _)| timing.beat_time(end_beat));
+                match end {
+                    Some(end) if end > start => {
+                        hit_objects.push(HitObject::hold(lane, start, end - start));
+                    }
+                    Some(_) => {
+                        diagnostics.push(ImportDiagnostic::new(
+                            Some(pair[0].1.line),
+                            "Long note ends on the same beat it starts, converted to a tap note",
+                        ));
+                        hit_objects.push(HitObject::note(lane, start));
+                    }
+                    None => {
+                        diagnostics.push(ImportDiagnostic::new(
+                            Some(pair[0].1.line),
+                            "Long note has no end, converted to a tap note",
+                        ));
+                        hit_objects.push(HitObject::note(lane, start));
+                    }
+                }
+            }
+        }
+        hit_objects.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.lane.cmp(&b.lane)));
+
+        for (what, count) in ignored {
+            diagnostics.push(ImportDiagnostic::new(
+                None,
+                format!("{} {} ignored", count, what),
+            ));
+        }
+        if !bgm.is_empty() {
+            diagnostics.push(ImportDiagnostic::new(
+                None,
+                format!("{} background sounds (channel 01) dropped", bgm.len()),
+            ));
+        }
+        // The song audio isn't dropped
+        let keysounds = self.wavs.keys().filter(|id| Some(*id) != audio_id).count();
+        if keysounds > 0 {
+            diagnostics.push(ImportDiagnostic::new(
+                None,
+                format!("{} keysounds (#WAV) dropped", keysounds),
+            ));
+        }
+
+        self.diagnostics.extend(diagnostics);
+
+        let mut map = ZuchsyaMap::new();
+        map.difficulty.keys = keys;
+        map.timing = timing.timing_points();
+        map.hit_objects = hit_objects;
+        map.audio.file = audio_file;
+        self.apply_headers(&mut map);
+
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Tempo map from #BPM, channels 03/08 (BPM changes), 09 (stops) and 02 (measure lengths)
+    fn build_timing(
+        &mut self,
+        starts: &[f64],
+        last_measure: usize,
+    ) -> Result<BeatTiming, ConvertError> {
+        let initial_bpm = match self.headers.get("BPM") {
+            Some((line, value)) => parse_num(*line, "BPM", value)?,
+            None => DEFAULT_BPM,
+        };
+
+        let mut bpms = vec![(0.0, initial_bpm)];
+        let mut stop_objects = Vec::new();
+        for obj in &self.objects {
+            let beat = starts[obj.measure] + self.measure_beats(obj.measure) * obj.position;
+            match obj.channel.as_str() {
+                "03" => match u8::from_str_radix(&obj.id, 16) {
+                    Ok(bpm) if bpm > 0 => bpms.push((beat, bpm as f64)),
+                    _ => self.diagnostics.push(ImportDiagnostic::new(
+                        Some(obj.line),
+                        format!("Invalid BPM change {}", obj.id),
+                    )),
+                },
+                "08" => match self.bpms.get(&obj.id) {
+                    Some(&bpm) if bpm > 0.0 => bpms.push((beat, bpm)),
+                    _ => self.diagnostics.push(ImportDiagnostic::new(
+                        Some(obj.line),
+                        format!("BPM change #BPM{} is missing or not positive", obj.id),
+                    )),
+                },
+                "09" => match self.stops.get(&obj.id) {
+                    Some(&units) => stop_objects.push((beat, units)),
+                    None => self.diagnostics.push(ImportDiagnostic::new(
+                        Some(obj.line),
+                        format!("Stop #STOP{} is not defined", obj.id),
+                    )),
+                },
+                _ => {}
+            }
+        }
+        bpms.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Restart the measure grid wherever the measure length changes
+        let mut measures = Vec::new();
+        let mut previous_beats = None;
+        for (measure, &start) in starts.iter().enumerate().take(last_measure + 1) {
+            let beats = self.measure_beats(measure);
+            if previous_beats != Some(beats) {
+                measures.push((start, beats.round().max(1.0) as u8));
+                previous_beats = Some(beats);
+            }
+        }
+
+        let mut timing = BeatTiming {
+            offset_ms: 0.0,
+            bpms,
+            stops: Vec::new(),
+            delays: Vec::new(),
+            measures,
+        };
+
+        // Stop lengths are in beats, so they depend on the BPM at the stop
+        let mut stops: Vec<(f64, f64)> = stop_objects
+            .into_iter()
+            .map(|(beat, units)| {
+                (
+                    beat,
+                    units / STOP_UNITS_PER_BEAT * 60000.0 / timing.bpm_at(beat),
+                )
+            })
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        timing.stops = stops;
+
+        Ok(timing)
+    }
+
+    /// Map channel digits (PMS 2P digits offset by 10) to lanes
+    fn lane_layout(&self, kind: BmsKind) -> HashMap<u8, u8> {
+        let used = |digit: u8| {
+            self.objects.iter().any(|obj| {
+                let bytes = obj.channel.as_bytes();
+                matches!(bytes[0], b'1' | b'5') && bytes[1] == digit
+            })
+        };
+
+        match kind {
+            // 1P 1-5, then 2P 2-5
+            BmsKind::PopN => [
+                b'1',
+                b'2',
+                b'3',
+                b'4',
+                b'5',
+                b'2' + 10,
+                b'3' + 10,
+                b'4' + 10,
+                b'5' + 10,
+            ]
+            .into_iter()
+            .zip(0..)
+            .collect(),
+            BmsKind::Beat => {
+                let scratch = used(b'6');
+                let seven = used(b'8') || used(b'9');
+                let mut keys = vec![b'1', b'2', b'3', b'4', b'5'];
+                if seven {
+                    keys.extend([b'8', b'9']);
+                }
+                let first = u8::from(scratch);
+
+                let mut layout: HashMap<u8, u8> = keys.into_iter().zip(first..).collect();
+                if scratch {
+                    layout.insert(b'6', 0);
+                }
+                layout
+            }
+        }
+    }
+
+    fn apply_headers(&self, map: &mut ZuchsyaMap) {
+        let header = |key: &str| {
+            self.headers
+                .get(key)
+                .map(|(_, value)| value.clone())
+                .filter(|value| !value.is_empty())
+        };
+
+        map.metadata.title = header("TITLE").unwrap_or_default();
+        map.metadata.artist = header("ARTIST").unwrap_or_default();
+        map.metadata.creator = header("SUBARTIST").unwrap_or_default();
+        map.metadata.tags = header("GENRE")
+            .map(|genre| genre.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        map.background = header("STAGEFILE").or_else(|| header("BACKBMP"));
+
+        let difficulty = header("DIFFICULTY").and_then(|d| match d.as_str() {
+            "1" => Some("Beginner"),
+            "2" => Some("Normal"),
+            "3" => Some("Hyper"),
+            "4" => Some("Another"),
+            "5" => Some("Insane"),
+            _ => None,
+        });
+        let level = header("PLAYLEVEL").map(|level| format!("Lv.{}", level));
+        map.metadata.difficulty_name = match (header("SUBTITLE"), difficulty, level) {
+            (Some(subtitle), _, _) => subtitle,
+            (None, Some(difficulty), Some(level)) => format!("{} {}", difficulty, level),
+            (None, Some(difficulty), None) => difficulty.to_string(),
+            (None, None, Some(level)) => level,
+            (None, None, None) => String::new(),
+        };
+    }
+}
+
+/// Split `mmmcc:data` into measure, channel and data
+fn split_channel_line(command: &str) -> Option<(usize, String, &str)> {
+    let (head, data) = command.split_once(':')?;
+    if head.len() != 5 || !head.is_ascii() {
+        return None;
+    }
+    let measure = head[..3].parse().ok()?;
+    Some((measure, head[3..].to_ascii_uppercase(), data.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Convert a BMS body at 120 BPM (2000ms per 4/4 measure)
+    fn convert(body: &str, kind: BmsKind) -> Imported {
+        let content = format!("#TITLE Test\n#ARTIST Zuchsya\n#BPM 120\n{}\n", body);
+        ZuchsyaMap::from_bms(&content, kind).unwrap()
+    }
+
+    /// (lane, time, duration) of every hit object
+    fn objects(map: &ZuchsyaMap) -> Vec<(u8, f64, Option<f64>)> {
+        map.hit_objects
+            .iter()
+            .map(|obj| (obj.lane, obj.time, obj.duration))
+            .collect()
+    }
+
+    fn has_diagnostic(imported: &Imported, text: &str) -> bool {
+        imported
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains(text))
+    }
+
+    #[test]
+    fn random_uses_branch_1() {
+        let imported = convert(
+            "#RANDOM 2\n#IF 1\n#00011:01\n#ENDIF\n#IF 2\n#00012:01\n#ENDIF\n#ENDRANDOM",
+            BmsKind::Beat,
+        );
+        assert_eq!(objects(&imported.map), [(0, 0.0, None)]);
+        assert!(has_diagnostic(&imported, "branch 1"));
+    }
+
+    #[test]
+    fn measure_length() {
+        let imported = convert("#00002:0.75\n#00011:01\n#00111:01", BmsKind::Beat);
+        let map = &imported.map;
+        assert_eq!(objects(map), [(0, 0.0, None), (0, 1500.0, None)]);
+
+        let timing: Vec<_> = map.timing.iter().map(|t| (t.time, t.signature)).collect();
+        assert_eq!(timing, [(0.0, 3), (1500.0, 4)]);
+    }
+
+    #[test]
+    fn bpm_changes_and_stops() {
+        let imported = convert(
+            "#BPM01 240\n#STOP01 48\n#00108:01\n#00109:01\n#00111:0101\n#00203:3C\n#00211:01",
+            BmsKind::Beat,
+        );
+        // 240 BPM from measure 1, with a one beat stop (250ms) after its first note;
+        // hex BPM 3C (60) from measure 2
+        assert_eq!(
+            objects(&imported.map),
+            [(0, 2000.0, None), (0, 2750.0, None), (0, 3250.0, None)]
+        );
+
+        // The beat grid restarts after the stop
+        let timing: Vec<_> = imported
+            .map
+            .timing
+            .iter()
+            .map(|t| (t.time, t.bpm))
+            .collect();
+        assert_eq!(timing, [(0.0, 120.0), (2250.0, 240.0), (3250.0, 60.0)]);
+    }
+
+    #[test]
+    fn seven_keys_with_scratch_is_8k() {
+        let imported = convert(
+            "#00016:01\n#00111:01\n#00112:01\n#00113:01\n#00114:01\n#00115:01\n\
+             #00118:01\n#00119:01",
+            BmsKind::Beat,
+        );
+        let map = &imported.map;
+        assert_eq!(map.difficulty.keys, 8);
+        let lanes: Vec<_> = map.hit_objects.iter().map(|obj| obj.lane).collect();
+        assert_eq!(lanes, [0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn five_keys_without_scratch() {
+        let imported = convert("#00011:01\n#00015:01", BmsKind::Beat);
+        assert_eq!(imported.map.difficulty.keys, 5);
+    }
+
+    #[test]
+    fn pms_layout() {
+        let channels = ["11", "12", "13", "14", "15", "22", "23", "24", "25"];
+        let body: Vec<String> = channels
+            .iter()
+            .enumerate()
+            .map(|(measure, channel)| format!("#{:03}{}:01", measure, channel))
+            .collect();
+        let imported = convert(&body.join("\n"), BmsKind::PopN);
+        let map = &imported.map;
+        assert_eq!(map.difficulty.keys, 9);
+        let lanes: Vec<_> = map.hit_objects.iter().map(|obj| obj.lane).collect();
+        assert_eq!(lanes, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // 2P notes are not part of the beat layout
+        let beat = convert(&body.join("\n"), BmsKind::Beat);
+        assert_eq!(beat.map.difficulty.keys, 5);
+        assert!(has_diagnostic(&beat, "4 2P notes ignored"));
+    }
+
+    #[test]
+    fn lnobj_and_long_note_channels() {
+        let imported = convert("#LNOBJ ZZ\n#00011:01ZZ\n#00052:0101", BmsKind::Beat);
+        assert_eq!(
+            objects(&imported.map),
+            [(0, 0.0, Some(1000.0)), (1, 0.0, Some(1000.0))]
+        );
+    }
+
+    #[test]
+    fn zero_length_long_notes_are_taps() {
+        let imported = convert(
+            "#LNOBJ ZZ\n#00011:01\n#00011:ZZ\n#00052:01\n#00052:01",
+            BmsKind::Beat,
+        );
+        assert_eq!(objects(&imported.map), [(0, 0.0, None), (1, 0.0, None)]);
+        assert!(has_diagnostic(&imported, "#LNOBJ end on the same beat"));
+        assert!(has_diagnostic(&imported, "Long note ends on the same beat"));
+    }
+
+    #[test]
+    fn song_audio_is_not_a_dropped_keysound() {
+        let imported = convert(
+            "#WAV01 song.ogg\n#WAV02 kick.wav\n#00001:01\n#00011:02",
+            BmsKind::Beat,
+        );
+        assert_eq!(imported.map.audio.file, "song.ogg");
+        assert!(has_diagnostic(&imported, "1 keysounds (#WAV) dropped"));
+    }
+}
@@ -0,0 +1,90 @@
+//! Converters between .zuchsya and other rhythm game formats
+//!
+//! - osu!mania (.osu)
+//! - StepMania (.sm, .ssc)
+//! - Quaver (.qua)
+//! - BMS family (.bms, .bme, .bml, .pms)
+
+mod beats;
+pub mod bms;
+pub mod osu;
+pub mod quaver;
+pub mod stepmania;
+
+use std::fmt;
+
+use crate::{BeatmapError, ZuchsyaMap};
+
+pub use bms::BmsKind;
+
+/// Beatmap conversion errors
+#[derive(Debug, thiserror::Error)]
+pub enum ConvertError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parse error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Parse error on line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Unsupported game mode: {0}")]
+    UnsupportedMode(String),
+    /// For formats without line numbers (e.g. YAML read with serde)
+    #[error("Hit object at {time}ms has lane {lane} outside 1-{keys}")]
+    LaneOutOfRange { time: f64, lane: u8, keys: u8 },
+    #[error(transparent)]
+    Beatmap(#[from] BeatmapError),
+}
+
+impl ConvertError {
+    fn parse(line: usize, message: impl Into<String>) -> Self {
+        Self::Parse {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Non-fatal problem found while importing (something dropped or approximated)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportDiagnostic {
+    /// Line in the source file, if the problem belongs to one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ImportDiagnostic {
+    pub fn new(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ImportDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Converted beatmap with the problems found while importing it
+#[derive(Debug, Clone)]
+pub struct Imported {
+    pub map: ZuchsyaMap,
+    pub diagnostics: Vec<ImportDiagnostic>,
+}
+
+/// Parse a number, reporting the line and field on failure
+fn parse_num<T: std::str::FromStr>(
+    line: usize,
+    what: &str,
+    value: &str,
+) -> Result<T, ConvertError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConvertError::parse(line, format!("Invalid {}: '{}'", what, value)))
+}
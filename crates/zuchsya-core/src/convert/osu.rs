@@ -0,0 +1,658 @@
+//! osu!mania (.osu) import and export
+//!
+//! Only mania maps (`Mode: 3`) can be converted. Uninherited timing points become
+//! `TimingPoint`s, inherited ones become `ScrollVelocity` changes (and back on export).
+
+use std::path::Path;
+
+use super::{ConvertError, parse_num};
+use crate::{BreakPeriod, EditorInfo, HitObject, ScrollVelocity, TimingPoint, ZuchsyaMap};
+
+/// osu! game mode id for mania
+const MANIA_MODE: u32 = 3;
+
+/// Playfield width hit object x positions are spread over
+const PLAYFIELD_WIDTH: f64 = 512.0;
+
+/// Hit object type flag for tap notes
+const NOTE_FLAG: u32 = 1;
+
+/// Hit object type flag for hold notes
+const HOLD_FLAG: u32 = 128;
+
+/// .osu format version written on export
+const EXPORT_FORMAT_VERSION: u32 = 14;
+
+/// Prefix of set IDs taken from osu! beatmap set IDs
+const OSU_SET_ID_PREFIX: &str = "osu:";
+
+impl ZuchsyaMap {
+    /// Load and convert an osu!mania .osu file
+    pub fn load_osu(path: &Path) -> Result<Self, ConvertError> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_osu(&content)
+    }
+
+    /// Convert osu!mania .osu file contents
+    pub fn from_osu(content: &str) -> Result<Self, ConvertError> {
+        let mut parser = OsuParser::default();
+
+        for (index, raw) in content.lines().enumerate() {
+            parser.parse_line(index + 1, raw)?;
+        }
+
+        parser.finish()
+    }
+
+    /// Export as an osu!mania .osu file
+    pub fn save_osu(&self, path: &Path) -> Result<(), ConvertError> {
+        std::fs::write(path, self.to_osu())?;
+        Ok(())
+    }
+
+    /// Serialize as osu!mania .osu (v14) file contents
+    pub fn to_osu(&self) -> String {
+        let metadata = &self.metadata;
+        let mut lines = vec![format!("osu file format v{}", EXPORT_FORMAT_VERSION)];
+
+        lines.push(String::new());
+        lines.push("[General]".into());
+        lines.push(format!("AudioFilename: {}", self.audio.file));
+        lines.push("AudioLeadIn: 0".into());
+        lines.push(format!("PreviewTime: {}", self.audio.preview_time));
+        lines.push("Countdown: 0".into());
+        lines.push("SampleSet: Soft".into());
+        lines.push("StackLeniency: 0.7".into());
+        lines.push(format!("Mode: {}", MANIA_MODE));
+        lines.push("LetterboxInBreaks: 0".into());
+        lines.push("SpecialStyle: 0".into());
+        lines.push("WidescreenStoryboard: 0".into());
+
+        if let Some(editor) = &self.editor
+            && !editor.bookmarks.is_empty()
+        {
+            let bookmarks: Vec<String> = editor.bookmarks.iter().map(|b| b.to_string()).collect();
+            lines.push(String::new());
+            lines.push("[Editor]".into());
+            lines.push(format!("Bookmarks: {}", bookmarks.join(",")));
+        }
+
+        lines.push(String::new());
+        lines.push("[Metadata]".into());
+        lines.push(format!("Title:{}", metadata.title));
+        lines.push(format!(
+            "TitleUnicode:{}",
+            metadata.title_unicode.as_deref().unwrap_or(&metadata.title)
+        ));
+        lines.push(format!("Artist:{}", metadata.artist));
+        lines.push(format!(
+            "ArtistUnicode:{}",
+            metadata
+                .artist_unicode
+                .as_deref()
+                .unwrap_or(&metadata.artist)
+        ));
+        lines.push(format!("Creator:{}", metadata.creator));
+        lines.push(format!("Version:{}", metadata.difficulty_name));
+        lines.push(format!(
+            "Source:{}",
+            metadata.source.as_deref().unwrap_or_default()
+        ));
+        lines.push(format!("Tags:{}", metadata.tags.join(" ")));
+        lines.push("BeatmapID:0".into());
+        let set_id = metadata
+            .set_id
+            .as_deref()
+            .and_then(|id| id.strip_prefix(OSU_SET_ID_PREFIX))
+            .and_then(|id| id.parse::<i64>().ok())
+            .unwrap_or(-1);
+        lines.push(format!("BeatmapSetID:{}", set_id));
+
+        lines.push(String::new());
+        lines.push("[Difficulty]".into());
+        lines.push(format!("HPDrainRate:{}", self.difficulty.hp));
+        lines.push(format!("CircleSize:{}", self.difficulty.keys));
+        lines.push(format!("OverallDifficulty:{}", self.difficulty.od));
+        lines.push("ApproachRate:5".into());
+        lines.push("SliderMultiplier:1.4".into());
+        lines.push("SliderTickRate:1".into());
+
+        lines.push(String::new());
+        lines.push("[Events]".into());
+        if let Some(background) = &self.background {
+            lines.push(format!("0,0,\"{}\",0,0", background));
+        }
+        if let Some(editor) = &self.editor {
+            for period in &editor.breaks {
+                lines.push(format!("2,{},{}", period.start, period.end));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push("[TimingPoints]".into());
+        lines.extend(self.osu_timing_points());
+
+        lines.push(String::new());
+        lines.push("[HitObjects]".into());
+        let keys = self.difficulty.keys.max(1);
+        for obj in &self.hit_objects {
+            // Center of the column, so importing maps it back to the same lane
+            let x = ((obj.lane as f64 + 0.5) * PLAYFIELD_WIDTH / keys as f64).floor();
+            let time = obj.time.round();
+            match obj.duration {
+                Some(duration) => lines.push(format!(
+                    "{},192,{},{},0,{}:0:0:0:0:",
+                    x,
+                    time,
+                    HOLD_FLAG,
+                    (obj.time + duration).round()
+                )),
+                None => lines.push(format!("{},192,{},{},0,0:0:0:0:", x, time, NOTE_FLAG)),
+            }
+        }
+
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    /// Timing points and scroll velocities as .osu timing point lines
+    ///
+    /// osu! resets the scroll speed at every uninherited point, so an active scroll
+    /// velocity is repeated after each BPM change.
+    fn osu_timing_points(&self) -> Vec<String> {
+        // (time, is SV, index) - BPM changes sort first when both are at the same time
+        let mut events: Vec<(f64, bool, usize)> = self
+            .timing
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.time, false, i))
+            .chain(
+                self.scroll_velocities
+                    .iter()
+                    .enumerate()
+                    .map(|(i, sv)| (sv.time, true, i)),
+            )
+            .collect();
+        events.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut lines = Vec::new();
+        let mut signature = 4;
+        let mut multiplier = 1.0;
+
+        for (n, &(time, is_sv, index)) in events.iter().enumerate() {
+            if is_sv {
+                multiplier = self.scroll_velocities[index].multiplier;
+                lines.push(inherited_line(time, multiplier, signature));
+                continue;
+            }
+
+            let point = &self.timing[index];
+            signature = point.signature;
+            lines.push(format!(
+                "{},{},{},1,0,100,1,0",
+                time,
+                point.beat_length(),
+                signature
+            ));
+
+            let sv_follows = events[n + 1..]
+                .iter()
+                .take_while(|e| e.0 == time)
+                .any(|e| e.1);
+            if multiplier != 1.0 && !sv_follows {
+                lines.push(inherited_line(time, multiplier, signature));
+            }
+        }
+
+        lines
+    }
+}
+
+/// Inherited (scroll velocity) timing point line
+fn inherited_line(time: f64, multiplier: f64, signature: u8) -> String {
+    let beat_length = -100.0 / multiplier.clamp(0.01, 10.0);
+    format!("{},{},{},1,0,100,0,0", time, beat_length, signature)
+}
+
+/// Timing point as written in the file
+struct RawTimingPoint {
+    time: f64,
+    beat_length: f64,
+    meter: u8,
+    uninherited: bool,
+}
+
+/// Hit object as written in the file (lane is resolved once the key count is known)
+struct RawHitObject {
+    x: f64,
+    time: f64,
+    end_time: Option<f64>,
+}
+
+#[derive(Default)]
+struct OsuParser {
+    map: ZuchsyaMap,
+    section: String,
+    seen_header: bool,
+    mode: u32,
+    keys: Option<f64>,
+    /// Line the key count (CircleSize) was read from
+    keys_line: usize,
+    timing_points: Vec<RawTimingPoint>,
+    hit_objects: Vec<RawHitObject>,
+    breaks: Vec<BreakPeriod>,
+}
+
+impl OsuParser {
+    fn parse_line(&mut self, line: usize, raw: &str) -> Result<(), ConvertError> {
+        // The first line may start with a UTF-8 BOM
+        let text = raw.trim_start_matches('\u{feff}').trim();
+        if text.is_empty() || text.starts_with("//") {
+            return Ok(());
+        }
+
+        if !self.seen_header {
+            if !text.starts_with("osu file format v") {
+                return Err(ConvertError::parse(
+                    line,
+                    "Missing 'osu file format' header",
+                ));
+            }
+            self.seen_header = true;
+            return Ok(());
+        }
+
+        if text.starts_with('[') && text.ends_with(']') {
+            self.section = text[1..text.len() - 1].to_string();
+            return Ok(());
+        }
+
+        match self.section.as_str() {
+            "General" | "Metadata" | "Difficulty" => {
+                let Some((key, value)) = text.split_once(':') else {
+                    return Err(ConvertError::parse(
+                        line,
+                        format!("Expected 'Key: Value', got '{}'", text),
+                    ));
+                };
+                self.parse_key_value(line, key.trim(), value.trim())
+            }
+            "Events" => self.parse_event(line, text),
+            "TimingPoints" => self.parse_timing_point(line, text),
+            "HitObjects" => self.parse_hit_object(line, text),
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_key_value(&mut self, line: usize, key: &str, value: &str) -> Result<(), ConvertError> {
+        let metadata = &mut self.map.metadata;
+        match (self.section.as_str(), key) {
+            ("General", "AudioFilename") => self.map.audio.file = value.to_string(),
+            ("General", "PreviewTime") => {
+                self.map.audio.preview_time = parse_num(line, key, value)?
+            }
+            ("General", "Mode") => self.mode = parse_num(line, key, value)?,
+            ("Metadata", "Title") => metadata.title = value.to_string(),
+            ("Metadata", "TitleUnicode") => metadata.title_unicode = non_empty(value),
+            ("Metadata", "Artist") => metadata.artist = value.to_string(),
+            ("Metadata", "ArtistUnicode") => metadata.artist_unicode = non_empty(value),
+            ("Metadata", "Creator") => metadata.creator = value.to_string(),
+            ("Metadata", "Version") => metadata.difficulty_name = value.to_string(),
+            ("Metadata", "Source") => metadata.source = non_empty(value),
+            ("Metadata", "Tags") => {
+                metadata.tags = value.split_whitespace().map(str::to_string).collect();
+            }
+            ("Metadata", "BeatmapSetID") => {
+                let id: i64 = parse_num(line, key, value)?;
+                metadata.set_id = (id > 0).then(|| format!("{}{}", OSU_SET_ID_PREFIX, id));
+            }
+            ("Difficulty", "CircleSize") => {
+                self.keys = Some(parse_num(line, key, value)?);
+                self.keys_line = line;
+            }
+            ("Difficulty", "OverallDifficulty") => {
+                self.map.difficulty.od = parse_num(line, key, value)?
+            }
+            ("Difficulty", "HPDrainRate") => self.map.difficulty.hp = parse_num(line, key, value)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_event(&mut self, line: usize, text: &str) -> Result<(), ConvertError> {
+        let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+        match fields[0] {
+            // Background: 0,0,"file",x,y
+            "0" if fields.len() >= 3 => {
+                self.map.background = non_empty(fields[2].trim_matches('"'));
+            }
+            // Break: 2,start,end
+            "2" | "Break" if fields.len() >= 3 => {
+                self.breaks.push(BreakPeriod {
+                    start: parse_num(line, "break start", fields[1])?,
+                    end: parse_num(line, "break end", fields[2])?,
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_timing_point(&mut self, line: usize, text: &str) -> Result<(), ConvertError> {
+        // time,beatLength,meter,sampleSet,sampleIndex,volume,uninherited,effects
+        let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+        if fields.len() < 2 {
+            return Err(ConvertError::parse(
+                line,
+                "Timing point needs at least time and beat length",
+            ));
+        }
+
+        let beat_length: f64 = parse_num(line, "beat length", fields[1])?;
+        let uninherited = match fields.get(6) {
+            Some(value) => *value == "1",
+            // Old files without the field mark inherited points with a negative beat length
+            None => beat_length > 0.0,
+        };
+
+        self.timing_points.push(RawTimingPoint {
+            time: parse_num(line, "time", fields[0])?,
+            beat_length,
+            meter: match fields.get(2) {
+                Some(value) => parse_num(line, "meter", value)?,
+                None => 4,
+            },
+            uninherited,
+        });
+        Ok(())
+    }
+
+    fn parse_hit_object(&mut self, line: usize, text: &str) -> Result<(), ConvertError> {
+        // x,y,time,type,hitSound,objectParams,hitSample
+        let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+        if fields.len() < 4 {
+            return Err(ConvertError::parse(
+                line,
+                "Hit object needs at least x, y, time and type",
+            ));
+        }
+
+        let time: f64 = parse_num(line, "time", fields[2])?;
+        let object_type: u32 = parse_num(line, "type", fields[3])?;
+
+        let end_time = if object_type & HOLD_FLAG != 0 {
+            // Hold params: endTime:hitSample
+            let Some(params) = fields.get(5) else {
+                return Err(ConvertError::parse(
+                    line,
+                    "Hold note is missing its end time",
+                ));
+            };
+            let end = params.split(':').next().unwrap_or_default();
+            Some(parse_num(line, "end time", end)?)
+        } else {
+            None
+        };
+
+        self.hit_objects.push(RawHitObject {
+            x: parse_num(line, "x", fields[0])?,
+            time,
+            end_time,
+        });
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<ZuchsyaMap, ConvertError> {
+        if !self.seen_header {
+            return Err(ConvertError::parse(1, "Missing 'osu file format' header"));
+        }
+        if self.mode != MANIA_MODE {
+            return Err(ConvertError::UnsupportedMode(mode_name(self.mode)));
+        }
+
+        // Lanes can't be resolved without a usable key count
+        let keys = self.keys.unwrap_or(4.0).round();
+        if !(1.0..=10.0).contains(&keys) {
+            return Err(ConvertError::parse(
+                self.keys_line,
+                format!("Key count {} is outside 1-10", keys),
+            ));
+        }
+        let keys = keys as u8;
+        self.map.difficulty.keys = keys;
+
+        self.convert_timing_points();
+
+        let mut hit_objects = Vec::with_capacity(self.hit_objects.len());
+        for raw in &self.hit_objects {
+            let lane = ((raw.x * keys as f64 / PLAYFIELD_WIDTH).floor() as i64)
+                .clamp(0, keys as i64 - 1) as u8;
+            let object = match raw.end_time {
+                Some(end) if end > raw.time => HitObject::hold(lane, raw.time, end - raw.time),
+                // Zero-length holds play as taps
+                _ => HitObject::note(lane, raw.time),
+            };
+            hit_objects.push(object);
+        }
+        hit_objects.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.lane.cmp(&b.lane)));
+        self.map.hit_objects = hit_objects;
+
+        if !self.breaks.is_empty() {
+            self.map.editor = Some(EditorInfo {
+                breaks: self.breaks,
+                ..Default::default()
+            });
+        }
+
+        self.map.validate()?;
+        Ok(self.map)
+    }
+
+    /// Split osu! timing points into BPM changes and scroll velocities
+    fn convert_timing_points(&mut self) {
+        // Uninherited points come first when both are at the same time
+        self.timing_points.sort_by(|a, b| {
+            a.time
+                .total_cmp(&b.time)
+                .then(b.uninherited.cmp(&a.uninherited))
+        });
+
+        let mut timing = Vec::new();
+        let mut scroll_velocities: Vec<ScrollVelocity> = Vec::new();
+        let mut current_sv = 1.0;
+
+        for point in &self.timing_points {
+            let multiplier = if point.uninherited {
+                if point.beat_length > 0.0 {
+                    timing.push(TimingPoint::with_signature(
+                        point.time,
+                        60000.0 / point.beat_length,
+                        point.meter.max(1),
+                    ));
+                }
+                // A new timing section resets the scroll speed
+                1.0
+            } else if point.beat_length < 0.0 {
+                (-100.0 / point.beat_length).clamp(0.01, 10.0)
+            } else {
+                1.0
+            };
+
+            if multiplier == current_sv {
+                continue;
+            }
+            current_sv = multiplier;
+
+            match scroll_velocities.last_mut() {
+                Some(last) if last.time == point.time => last.multiplier = multiplier,
+                _ => scroll_velocities.push(ScrollVelocity::new(point.time, multiplier)),
+            }
+        }
+
+        self.map.timing = timing;
+        self.map.scroll_velocities = scroll_velocities;
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn mode_name(mode: u32) -> String {
+    match mode {
+        0 => "osu!standard".to_string(),
+        1 => "osu!taiko".to_string(),
+        2 => "osu!catch".to_string(),
+        other => format!("mode {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mania_map(circle_size: &str) -> String {
+        format!(
+            "osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:{}\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n",
+            circle_size
+        )
+    }
+
+    #[test]
+    fn converts_mania_map() {
+        let map = ZuchsyaMap::from_osu(&mania_map("4")).unwrap();
+        assert_eq!(map.difficulty.keys, 4);
+        assert_eq!(map.hit_objects.len(), 1);
+        assert_eq!(map.hit_objects[0].lane, 0);
+    }
+
+    #[test]
+    fn rejects_zero_keys() {
+        let err = ZuchsyaMap::from_osu(&mania_map("0")).unwrap_err();
+        assert!(matches!(err, ConvertError::Parse { line: 7, .. }), "{err}");
+    }
+
+    #[test]
+    fn rejects_too_many_keys() {
+        let err = ZuchsyaMap::from_osu(&mania_map("18")).unwrap_err();
+        assert!(matches!(err, ConvertError::Parse { line: 7, .. }), "{err}");
+    }
+
+    /// Map with a note in every lane followed by a hold in every lane
+    fn every_lane(keys: u8) -> ZuchsyaMap {
+        let mut map = ZuchsyaMap::new();
+        map.difficulty.keys = keys;
+        map.timing = vec![
+            TimingPoint::with_signature(0.0, 120.0, 4),
+            TimingPoint::with_signature(2000.0, 150.0, 3),
+        ];
+        map.scroll_velocities = vec![
+            ScrollVelocity::new(1000.0, 0.5),
+            ScrollVelocity::new(3000.0, 2.0),
+        ];
+        for lane in 0..keys {
+            map.hit_objects
+                .push(HitObject::note(lane, 100.0 * lane as f64));
+        }
+        for lane in 0..keys {
+            map.hit_objects
+                .push(HitObject::hold(lane, 4000.0 + 100.0 * lane as f64, 250.0));
+        }
+        map
+    }
+
+    /// Timing point lines as (time, beat length, meter, uninherited)
+    fn exported_timing_points(osu: &str) -> Vec<(f64, f64, u8, bool)> {
+        osu.lines()
+            .skip_while(|line| *line != "[TimingPoints]")
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                (
+                    fields[0].parse().unwrap(),
+                    fields[1].parse().unwrap(),
+                    fields[2].parse().unwrap(),
+                    fields[6] == "1",
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn export_round_trip() {
+        for keys in [1, 4, 5, 7, 8, 10] {
+            let map = every_lane(keys);
+            let osu = map.to_osu();
+
+            // Holds are type 128 with the end time first in the params
+            let holds = osu
+                .lines()
+                .filter(|line| line.split(',').nth(3) == Some("128"))
+                .count();
+            assert_eq!(holds, keys as usize, "{keys}K");
+
+            let imported = ZuchsyaMap::from_osu(&osu).unwrap();
+            assert_eq!(imported.difficulty.keys, keys);
+            let objects: Vec<_> = imported
+                .hit_objects
+                .iter()
+                .map(|obj| (obj.lane, obj.time, obj.duration))
+                .collect();
+            let expected: Vec<_> = map
+                .hit_objects
+                .iter()
+                .map(|obj| (obj.lane, obj.time, obj.duration))
+                .collect();
+            assert_eq!(objects, expected, "{keys}K");
+
+            let timing: Vec<_> = imported
+                .timing
+                .iter()
+                .map(|t| (t.time, t.bpm, t.signature))
+                .collect();
+            assert_eq!(timing, [(0.0, 120.0, 4), (2000.0, 150.0, 3)], "{keys}K");
+        }
+    }
+
+    #[test]
+    fn lanes_export_to_column_centers() {
+        let osu = every_lane(4).to_osu();
+        let x: Vec<&str> = osu
+            .lines()
+            .skip_while(|line| *line != "[HitObjects]")
+            .skip(1)
+            .take(4)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(x, ["64", "192", "320", "448"]);
+    }
+
+    #[test]
+    fn scroll_velocities_export_as_inherited_points() {
+        let osu = every_lane(4).to_osu();
+        assert_eq!(
+            exported_timing_points(&osu),
+            [
+                (0.0, 500.0, 4, true),
+                (1000.0, -200.0, 4, false),
+                // The BPM change resets the scroll speed in osu!, so the SV is repeated
+                (2000.0, 400.0, 3, true),
+                (2000.0, -200.0, 3, false),
+                (3000.0, -50.0, 3, false),
+            ]
+        );
+
+        let imported = ZuchsyaMap::from_osu(&osu).unwrap();
+        let scroll_velocities: Vec<_> = imported
+            .scroll_velocities
+            .iter()
+            .map(|sv| (sv.time, sv.multiplier))
+            .collect();
+        assert_eq!(
+            scroll_velocities,
+            [(1000.0, 0.5), (2000.0, 0.5), (3000.0, 2.0)]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Quaver (.qua) import
+//!
+//! .qua files are YAML, so they are read with serde. Quaver leaves out fields that
+//! have their default value (e.g. `StartTime: 0`), so every field is optional.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::ConvertError;
+use crate::{HitObject, ScrollVelocity, TimingPoint, ZuchsyaMap};
+
+impl ZuchsyaMap {
+    /// Load and convert a Quaver .qua file
+    pub fn load_quaver(path: &Path) -> Result<Self, ConvertError> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_quaver(&content)
+    }
+
+    /// Convert Quaver .qua file contents
+    pub fn from_quaver(content: &str) -> Result<Self, ConvertError> {
+        let qua: Qua = serde_yaml::from_str(content)?;
+        qua.convert()
+    }
+}
+
+/// Prefix of set IDs taken from Quaver map set IDs
+const QUAVER_SET_ID_PREFIX: &str = "quaver:";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct Qua {
+    audio_file: String,
+    song_preview_time: Option<i32>,
+    background_file: String,
+    mode: String,
+    has_scratch_key: bool,
+    title: String,
+    artist: String,
+    source: String,
+    tags: String,
+    creator: String,
+    difficulty_name: String,
+    map_set_id: i64,
+    initial_scroll_velocity: Option<f64>,
+    timing_points: Vec<QuaTimingPoint>,
+    slider_velocities: Vec<QuaSliderVelocity>,
+    hit_objects: Vec<QuaHitObject>,
+}
+
+impl Default for Qua {
+    fn default() -> Self {
+        Self {
+            audio_file: String::new(),
+            song_preview_time: None,
+            background_file: String::new(),
+            mode: "Keys4".to_string(),
+            has_scratch_key: false,
+            title: String::new(),
+            artist: String::new(),
+            source: String::new(),
+            tags: String::new(),
+            creator: String::new(),
+            difficulty_name: String::new(),
+            map_set_id: -1,
+            initial_scroll_velocity: None,
+            timing_points: Vec::new(),
+            slider_velocities: Vec::new(),
+            hit_objects: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct QuaTimingPoint {
+    start_time: f64,
+    bpm: f64,
+    /// `Quadruple`, `Triple` or a beat count
+    signature: Option<serde_yaml::Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct QuaSliderVelocity {
+    start_time: f64,
+    multiplier: f64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct QuaHitObject {
+    start_time: f64,
+    /// 1-based lane
+    lane: u8,
+    /// End time for long notes (0 = tap note)
+    end_time: f64,
+}
+
+impl Qua {
+    fn convert(self) -> Result<ZuchsyaMap, ConvertError> {
+        let keys = self
+            .mode
+            .strip_prefix("Keys")
+            .and_then(|keys| keys.parse::<u8>().ok())
+            .ok_or_else(|| ConvertError::UnsupportedMode(format!("Quaver {}", self.mode)))?;
+        let keys = keys + u8::from(self.has_scratch_key);
+
+        let mut map = ZuchsyaMap::new();
+        map.difficulty.keys = keys;
+
+        map.metadata.title = self.title;
+        map.metadata.artist = self.artist;
+        map.metadata.creator = self.creator;
+        map.metadata.difficulty_name = self.difficulty_name;
+        map.metadata.source = (!self.source.is_empty()).then_some(self.source);
+        map.metadata.tags = self.tags.split_whitespace().map(str::to_string).collect();
+        map.metadata.set_id =
+            (self.map_set_id > 0).then(|| format!("{}{}", QUAVER_SET_ID_PREFIX, self.map_set_id));
+
+        map.audio.file = self.audio_file;
+        if let Some(preview) = self.song_preview_time {
+            map.audio.preview_time = preview;
+        }
+        map.background = (!self.background_file.is_empty()).then_some(self.background_file);
+
+        map.timing = self
+            .timing_points
+            .iter()
+            .filter(|point| point.bpm > 0.0)
+            .map(|point| {
+                TimingPoint::with_signature(
+                    point.start_time,
+                    point.bpm,
+                    signature(&point.signature),
+                )
+            })
+            .collect();
+        map.timing.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut scroll_velocities: Vec<ScrollVelocity> = self
+            .slider_velocities
+            .iter()
+            .map(|sv| ScrollVelocity::new(sv.start_time, sv.multiplier))
+            .collect();
+        scroll_velocities.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        // Quaver scrolls at the initial velocity until the first SV point
+        if let Some(initial) = self.initial_scroll_velocity
+            && initial != 1.0
+        {
+            let first_object = self
+                .hit_objects
+                .iter()
+                .map(|obj| obj.start_time)
+                .fold(0.0, f64::min);
+            let start = map
+                .timing
+                .first()
+                .map_or(first_object, |t| t.time.min(first_object));
+            if scroll_velocities.first().is_none_or(|sv| sv.time > start) {
+                scroll_velocities.insert(0, ScrollVelocity::new(start, initial));
+            }
+        }
+        map.scroll_velocities = scroll_velocities;
+
+        let mut hit_objects = Vec::with_capacity(self.hit_objects.len());
+        for obj in &self.hit_objects {
+            if obj.lane < 1 || obj.lane > keys {
+                return Err(ConvertError::LaneOutOfRange {
+                    time: obj.start_time,
+                    lane: obj.lane,
+                    keys,
+                });
+            }
+
+            let lane = obj.lane - 1;
+            hit_objects.push(if obj.end_time > obj.start_time {
+                HitObject::hold(lane, obj.start_time, obj.end_time - obj.start_time)
+            } else {
+                HitObject::note(lane, obj.start_time)
+            });
+        }
+        hit_objects.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.lane.cmp(&b.lane)));
+        map.hit_objects = hit_objects;
+
+        map.validate()?;
+        Ok(map)
+    }
+}
+
+/// Beats per measure of a Quaver time signature
+fn signature(value: &Option<serde_yaml::Value>) -> u8 {
+    match value {
+        Some(serde_yaml::Value::String(name)) if name == "Triple" => 3,
+        Some(serde_yaml::Value::Number(n)) => n
+            .as_u64()
+            .and_then(|n| u8::try_from(n).ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(4),
+        _ => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objects(map: &ZuchsyaMap) -> Vec<(u8, f64, Option<f64>)> {
+        map.hit_objects
+            .iter()
+            .map(|obj| (obj.lane, obj.time, obj.duration))
+            .collect()
+    }
+
+    #[test]
+    fn lanes_are_one_based() {
+        let map = ZuchsyaMap::from_quaver(
+            "Mode: Keys4
+TimingPoints:
+- Bpm: 120
+HitObjects:
+- StartTime: 100
+  Lane: 1
+- StartTime: 200
+  Lane: 4
+  EndTime: 700
+",
+        )
+        .unwrap();
+        assert_eq!(map.difficulty.keys, 4);
+        assert_eq!(objects(&map), [(0, 100.0, None), (3, 200.0, Some(500.0))]);
+    }
+
+    #[test]
+    fn scratch_key_adds_a_lane() {
+        let map = ZuchsyaMap::from_quaver(
+            "Mode: Keys7
+HasScratchKey: true
+TimingPoints:
+- Bpm: 150
+HitObjects:
+- Lane: 8
+",
+        )
+        .unwrap();
+        assert_eq!(map.difficulty.keys, 8);
+        assert_eq!(objects(&map), [(7, 0.0, None)]);
+    }
+
+    #[test]
+    fn omitted_fields_use_defaults() {
+        // No mode, start times, signature or set ID
+        let map = ZuchsyaMap::from_quaver(
+            "Title: Defaults
+TimingPoints:
+- Bpm: 120
+SliderVelocities:
+- Multiplier: 0.5
+HitObjects:
+- Lane: 2
+",
+        )
+        .unwrap();
+        assert_eq!(map.difficulty.keys, 4);
+        assert_eq!(map.metadata.title, "Defaults");
+        assert_eq!(map.metadata.set_id, None);
+        let timing: Vec<_> = map
+            .timing
+            .iter()
+            .map(|t| (t.time, t.bpm, t.signature))
+            .collect();
+        assert_eq!(timing, [(0.0, 120.0, 4)]);
+        assert_eq!(map.scroll_velocities[0].time, 0.0);
+        assert_eq!(objects(&map), [(1, 0.0, None)]);
+    }
+
+    #[test]
+    fn initial_scroll_velocity() {
+        let qua = |first_sv: f64| {
+            format!(
+                "InitialScrollVelocity: 0.5
+TimingPoints:
+- StartTime: 0
+  Bpm: 120
+SliderVelocities:
+- StartTime: {}
+  Multiplier: 2
+HitObjects:
+- StartTime: 500
+  Lane: 1
+",
+                first_sv
+            )
+        };
+        let scroll_velocities = |map: ZuchsyaMap| -> Vec<(f64, f64)> {
+            map.scroll_velocities
+                .iter()
+                .map(|sv| (sv.time, sv.multiplier))
+                .collect()
+        };
+
+        let map = ZuchsyaMap::from_quaver(&qua(1000.0)).unwrap();
+        assert_eq!(scroll_velocities(map), [(0.0, 0.5), (1000.0, 2.0)]);
+
+        // Not needed when an SV point is already there at the start
+        let map = ZuchsyaMap::from_quaver(&qua(0.0)).unwrap();
+        assert_eq!(scroll_velocities(map), [(0.0, 2.0)]);
+    }
+
+    #[test]
+    fn lane_out_of_range() {
+        for lane in [0, 5] {
+            let err = ZuchsyaMap::from_quaver(&format!(
+                "Mode: Keys4
+TimingPoints:
+- Bpm: 120
+HitObjects:
+- StartTime: 300
+  Lane: {}
+",
+                lane
+            ))
+            .unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    ConvertError::LaneOutOfRange { time, lane: l, keys: 4 }
+                        if time == 300.0 && l == lane
+                ),
+                "{err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,667 @@
+//! StepMania (.sm / .ssc) import
+//!
+//! Every dance-single (4K) and dance-double (8K) chart in the file becomes its own
+//! `ZuchsyaMap`. Stops and delays are baked into note times, and the beat grid is
+//! restarted with a timing point after each pause.
+
+use std::path::Path;
+
+use super::beats::BeatTiming;
+use super::{ConvertError, parse_num};
+use crate::{HitObject, ZuchsyaMap};
+
+/// Beats per measure in StepMania note data
+const BEATS_PER_MEASURE: f64 = 4.0;
+
+impl ZuchsyaMap {
+    /// Load and convert all supported charts of a StepMania .sm or .ssc file
+    pub fn load_stepmania(path: &Path) -> Result<Vec<Self>, ConvertError> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_stepmania(&content)
+    }
+
+    /// Convert all supported charts of StepMania .sm or .ssc file contents
+    pub fn from_stepmania(content: &str) -> Result<Vec<Self>, ConvertError> {
+        let song = SmSong::parse(content)?;
+
+        let mut maps = Vec::new();
+        let mut unsupported = Vec::new();
+        for chart in &song.charts {
+            match keys_for(&chart.steps_type) {
+                Some(keys) => maps.push(song.convert_chart(chart, keys)?),
+                None => unsupported.push(chart.steps_type.clone()),
+            }
+        }
+
+        if maps.is_empty() {
+            return Err(ConvertError::UnsupportedMode(if unsupported.is_empty() {
+                "StepMania file without charts".to_string()
+            } else {
+                format!("StepMania {}", unsupported.join(", "))
+            }));
+        }
+
+        Ok(maps)
+    }
+}
+
+/// Key count for a StepMania steps type
+fn keys_for(steps_type: &str) -> Option<u8> {
+    match steps_type {
+        "dance-single" => Some(4),
+        "dance-double" => Some(8),
+        _ => None,
+    }
+}
+
+/// `#KEY:value;` tag with the line it starts on
+struct Tag {
+    line: usize,
+    key: String,
+    value: String,
+}
+
+/// Split file contents into tags, dropping `//` comments
+fn parse_tags(content: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut current: Option<Tag> = None;
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let mut text = raw.split("//").next().unwrap_or_default();
+
+        loop {
+            match current.as_mut() {
+                Some(tag) => match text.find(';') {
+                    Some(end) => {
+                        tag.value.push_str(&text[..end]);
+                        tags.extend(current.take());
+                        text = &text[end + 1..];
+                    }
+                    None => {
+                        tag.value.push_str(text);
+                        tag.value.push('\n');
+                        break;
+                    }
+                },
+                None => match text.find('#') {
+                    Some(start) => {
+                        let rest = &text[start + 1..];
+                        let (key, value_start) = match rest.find(':') {
+                            Some(colon) => (&rest[..colon], &rest[colon + 1..]),
+                            None => (rest, ""),
+                        };
+                        current = Some(Tag {
+                            line,
+                            key: key.trim().to_ascii_uppercase(),
+                            value: String::new(),
+                        });
+                        text = value_start;
+                    }
+                    None => break,
+                },
+            }
+        }
+    }
+
+    // Tolerate a missing semicolon on the last tag
+    tags.extend(current);
+    tags
+}
+
+/// Timing tags (song-wide, or per chart in .ssc files)
+#[derive(Clone, Default)]
+struct SmTimingTags {
+    offset: Option<(usize, String)>,
+    bpms: Option<(usize, String)>,
+    stops: Option<(usize, String)>,
+    delays: Option<(usize, String)>,
+}
+
+impl SmTimingTags {
+    /// Store a timing tag, returns false if the tag isn't a timing tag
+    fn set(&mut self, tag: &Tag) -> bool {
+        let slot = match tag.key.as_str() {
+            "OFFSET" => &mut self.offset,
+            "BPMS" => &mut self.bpms,
+            // Older .sm files call stops freezes
+            "STOPS" | "FREEZES" => &mut self.stops,
+            "DELAYS" => &mut self.delays,
+            _ => return false,
+        };
+        *slot = Some((tag.line, tag.value.clone()));
+        true
+    }
+
+    /// Override with the tags set in `chart`
+    fn merged(&self, chart: &SmTimingTags) -> SmTimingTags {
+        SmTimingTags {
+            offset: chart.offset.clone().or_else(|| self.offset.clone()),
+            bpms: chart.bpms.clone().or_else(|| self.bpms.clone()),
+            stops: chart.stops.clone().or_else(|| self.stops.clone()),
+            delays: chart.delays.clone().or_else(|| self.delays.clone()),
+        }
+    }
+}
+
+/// A single chart (`#NOTES` block)
+#[derive(Default)]
+struct SmChart {
+    steps_type: String,
+    description: String,
+    difficulty: String,
+    meter: String,
+    credit: String,
+    notes: String,
+    notes_line: usize,
+    timing: SmTimingTags,
+}
+
+#[derive(Default)]
+struct SmSong {
+    title: String,
+    title_translit: String,
+    subtitle: String,
+    artist: String,
+    artist_translit: String,
+    credit: String,
+    music: String,
+    background: String,
+    sample_start: Option<f64>,
+    timing: SmTimingTags,
+    charts: Vec<SmChart>,
+}
+
+impl SmSong {
+    fn parse(content: &str) -> Result<Self, ConvertError> {
+        let mut song = SmSong::default();
+        // .ssc chart being filled in (between #NOTEDATA and #NOTES)
+        let mut ssc_chart: Option<SmChart> = None;
+
+        for tag in parse_tags(content) {
+            let value = tag.value.trim();
+
+            if let Some(chart) = ssc_chart.as_mut() {
+                if chart.timing.set(&tag) {
+                    continue;
+                }
+                match tag.key.as_str() {
+                    "STEPSTYPE" => chart.steps_type = value.to_string(),
+                    "DESCRIPTION" => chart.description = value.to_string(),
+                    "DIFFICULTY" => chart.difficulty = value.to_string(),
+                    "METER" => chart.meter = value.to_string(),
+                    "CREDIT" => chart.credit = value.to_string(),
+                    "NOTES" | "NOTES2" => {
+                        chart.notes = tag.value.clone();
+                        chart.notes_line = tag.line;
+                        song.charts.extend(ssc_chart.take());
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            if song.timing.set(&tag) {
+                continue;
+            }
+            match tag.key.as_str() {
+                "TITLE" => song.title = value.to_string(),
+                "TITLETRANSLIT" => song.title_translit = value.to_string(),
+                "SUBTITLE" => song.subtitle = value.to_string(),
+                "ARTIST" => song.artist = value.to_string(),
+                "ARTISTTRANSLIT" => song.artist_translit = value.to_string(),
+                "CREDIT" => song.credit = value.to_string(),
+                "MUSIC" => song.music = value.to_string(),
+                "BACKGROUND" => song.background = value.to_string(),
+                "SAMPLESTART" => {
+                    song.sample_start = Some(parse_num(tag.line, "sample start", value)?);
+                }
+                "NOTEDATA" => ssc_chart = Some(SmChart::default()),
+                // .sm: type:description:difficulty:meter:radar:data
+                "NOTES" => song.charts.push(parse_sm_notes(&tag)?),
+                _ => {}
+            }
+        }
+
+        Ok(song)
+    }
+
+    fn convert_chart(&self, chart: &SmChart, keys: u8) -> Result<ZuchsyaMap, ConvertError> {
+        let timing = parse_timing(&self.timing.merged(&chart.timing), chart.notes_line)?;
+
+        let mut map = ZuchsyaMap::new();
+        map.difficulty.keys = keys;
+
+        // Translit fields hold the romanized names, the plain ones may be unicode
+        let title = if self.subtitle.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} {}", self.title, self.subtitle)
+        };
+        if self.title_translit.is_empty() {
+            map.metadata.title = title;
+        } else {
+            map.metadata.title = self.title_translit.clone();
+            map.metadata.title_unicode = Some(title);
+        }
+        if self.artist_translit.is_empty() {
+            map.metadata.artist = self.artist.clone();
+        } else {
+            map.metadata.artist = self.artist_translit.clone();
+            map.metadata.artist_unicode = Some(self.artist.clone());
+        }
+        map.metadata.creator = if chart.credit.is_empty() {
+            self.credit.clone()
+        } else {
+            chart.credit.clone()
+        };
+
+        let name = if chart.description.is_empty() {
+            format!("{} {}", chart.difficulty, chart.meter)
+                .trim()
+                .to_string()
+        } else {
+            chart.description.clone()
+        };
+        map.metadata.difficulty_name = if keys == 8 {
+            format!("Double {}", name)
+        } else {
+            name
+        };
+
+        map.audio.file = self.music.clone();
+        if let Some(start) = self.sample_start {
+            map.audio.preview_time = (start * 1000.0).round() as i32;
+        }
+        if !self.background.is_empty() {
+            map.background = Some(self.background.clone());
+        }
+
+        map.timing = timing.timing_points();
+        map.hit_objects = parse_note_data(&chart.notes, chart.notes_line, keys, &timing)?;
+
+        map.validate()?;
+        Ok(map)
+    }
+}
+
+/// Parse a .sm `#NOTES` tag into a chart
+fn parse_sm_notes(tag: &Tag) -> Result<SmChart, ConvertError> {
+    let fields: Vec<&str> = tag.value.splitn(6, ':').collect();
+    if fields.len() < 6 {
+        return Err(ConvertError::parse(
+            tag.line,
+            "#NOTES needs type, description, difficulty, meter, radar values and note data",
+        ));
+    }
+
+    // Note data starts after the newlines of the five header fields
+    let header_lines: usize = fields[..5].iter().map(|f| f.matches('\n').count()).sum();
+
+    Ok(SmChart {
+        steps_type: fields[0].trim().to_string(),
+        description: fields[1].trim().to_string(),
+        difficulty: fields[2].trim().to_string(),
+        meter: fields[3].trim().to_string(),
+        notes: fields[5].to_string(),
+        notes_line: tag.line + header_lines,
+        ..Default::default()
+    })
+}
+
+/// Build the beat timing of a chart from its timing tags
+fn parse_timing(tags: &SmTimingTags, chart_line: usize) -> Result<BeatTiming, ConvertError> {
+    let offset_ms = match &tags.offset {
+        // #OFFSET is the time of beat 0 relative to the music, negated, in seconds
+        Some((line, value)) => -parse_num::<f64>(*line, "offset", value.trim())? * 1000.0,
+        None => 0.0,
+    };
+
+    let Some((bpms_line, bpms_value)) = &tags.bpms else {
+        return Err(ConvertError::parse(chart_line, "Missing #BPMS"));
+    };
+    let bpms = parse_beat_pairs(*bpms_line, bpms_value)?;
+    if bpms.is_empty() {
+        return Err(ConvertError::parse(*bpms_line, "#BPMS has no BPM values"));
+    }
+    if let Some((beat, bpm)) = bpms.iter().find(|(_, bpm)| *bpm <= 0.0) {
+        return Err(ConvertError::parse(
+            *bpms_line,
+            format!(
+                "BPM {} at beat {} is not supported (warps can't be converted)",
+                bpm, beat
+            ),
+        ));
+    }
+
+    let pauses = |tag: &Option<(usize, String)>| -> Result<Vec<(f64, f64)>, ConvertError> {
+        match tag {
+            Some((line, value)) => Ok(parse_beat_pairs(*line, value)?
+                .into_iter()
+                .map(|(beat, seconds)| (beat, seconds * 1000.0))
+                .collect()),
+            None => Ok(Vec::new()),
+        }
+    };
+
+    Ok(BeatTiming {
+        offset_ms,
+        bpms,
+        stops: pauses(&tags.stops)?,
+        delays: pauses(&tags.delays)?,
+        measures: Vec::new(),
+    })
+}
+
+/// Parse `beat=value,beat=value` lists
+fn parse_beat_pairs(line: usize, value: &str) -> Result<Vec<(f64, f64)>, ConvertError> {
+    let mut pairs: Vec<(f64, f64)> = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((beat, value)) = entry.split_once('=') else {
+            return Err(ConvertError::parse(
+                line,
+                format!("Expected 'beat=value', got '{}'", entry),
+            ));
+        };
+        pairs.push((
+            parse_num(line, "beat", beat)?,
+            parse_num(line, "value", value)?,
+        ));
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(pairs)
+}
+
+/// Convert measure-based note data into hit objects
+fn parse_note_data(
+    data: &str,
+    start_line: usize,
+    keys: u8,
+    timing: &BeatTiming,
+) -> Result<Vec<HitObject>, ConvertError> {
+    let keys = keys as usize;
+    let mut hit_objects = Vec::new();
+    // Hold/roll heads waiting for their tail: (line, time)
+    let mut heads: Vec<Option<(usize, f64)>> = vec![None; keys];
+    let mut measure = 0usize;
+    let mut rows: Vec<(usize, &str)> = Vec::new();
+
+    let mut finish_measure = |measure: usize,
+                              rows: &mut Vec<(usize, &str)>|
+     -> Result<(), ConvertError> {
+        let row_count = rows.len();
+        for (r, (line, row)) in rows.drain(..).enumerate() {
+            if row.chars().count() != keys {
+                return Err(ConvertError::parse(
+                    line,
+                    format!("Expected {} columns, got '{}'", keys, row),
+                ));
+            }
+
+            let beat = BEATS_PER_MEASURE * (measure as f64 + r as f64 / row_count as f64);
+            let time = timing.beat_time(beat);
+
+            for (column, c) in row.chars().enumerate() {
+                let lane = column as u8;
+                match c {
+                    // Tap, lift and keysound notes
+                    '1' | 'L' | 'K' => hit_objects.push(HitObject::note(lane, time)),
+                    // Hold and roll heads
+                    '2' | '4' => heads[column] = Some((line, time)),
+                    '3' => {
+                        if let Some((_, head_time)) = heads[column].take() {
+                            hit_objects.push(HitObject::hold(lane, head_time, time - head_time));
+                        }
+                    }
+                    // Empty, mines and fakes
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    };
+
+    for (index, raw) in data.lines().enumerate() {
+        let line = start_line + index;
+        for (i, part) in raw.split(',').enumerate() {
+            if i > 0 {
+                finish_measure(measure, &mut rows)?;
+                measure += 1;
+            }
+            let row = part.trim();
+            if !row.is_empty() {
+                rows.push((line, row));
+            }
+        }
+    }
+    if !rows.is_empty() {
+        finish_measure(measure, &mut rows)?;
+    }
+
+    if let Some((line, _)) = heads.iter().flatten().next() {
+        return Err(ConvertError::parse(*line, "Hold has no tail"));
+    }
+
+    hit_objects.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.lane.cmp(&b.lane)));
+    Ok(hit_objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// .sm file with one chart
+    fn sm(timing: &str, steps_type: &str, notes: &str) -> String {
+        format!(
+            "#TITLE:Test;\n#ARTIST:Zuchsya;\n#MUSIC:song.ogg;\n{}\n\
+             #NOTES:\n     {}:\n     :\n     Hard:\n     9:\n     0,0,0,0,0:\n{};\n",
+            timing, steps_type, notes
+        )
+    }
+
+    fn single(timing: &str, notes: &str) -> ZuchsyaMap {
+        let mut maps = ZuchsyaMap::from_stepmania(&sm(timing, "dance-single", notes)).unwrap();
+        assert_eq!(maps.len(), 1);
+        maps.remove(0)
+    }
+
+    /// Assert (lane, time, duration) of every hit object
+    fn assert_objects(map: &ZuchsyaMap, expected: &[(u8, f64, Option<f64>)]) {
+        let actual: Vec<_> = map
+            .hit_objects
+            .iter()
+            .map(|obj| (obj.lane, obj.time, obj.duration))
+            .collect();
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            let close = |a: f64, e: f64| (a - e).abs() < 1e-6;
+            let same_duration = match (a.2, e.2) {
+                (Some(a), Some(e)) => close(a, e),
+                (a, e) => a == e,
+            };
+            assert!(
+                a.0 == e.0 && close(a.1, e.1) && same_duration,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    fn assert_timing(map: &ZuchsyaMap, expected: &[(f64, f64)]) {
+        let actual: Vec<_> = map.timing.iter().map(|t| (t.time, t.bpm)).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn multiple_bpms() {
+        let map = single(
+            "#OFFSET:0;\n#BPMS:0=120,4=240;",
+            "1000\n0100\n0010\n0001\n,\n1000\n0100\n0000\n0000",
+        );
+        assert_objects(
+            &map,
+            &[
+                (0, 0.0, None),
+                (1, 500.0, None),
+                (2, 1000.0, None),
+                (3, 1500.0, None),
+                (0, 2000.0, None),
+                (1, 2250.0, None),
+            ],
+        );
+        assert_timing(&map, &[(0.0, 120.0), (2000.0, 240.0)]);
+    }
+
+    #[test]
+    fn stops_pause_after_the_beat() {
+        let map = single("#BPMS:0=120;\n#STOPS:1=0.5;", "1000\n0100\n0010\n0001");
+        assert_objects(
+            &map,
+            &[
+                (0, 0.0, None),
+                (1, 500.0, None),
+                (2, 1500.0, None),
+                (3, 2000.0, None),
+            ],
+        );
+        // The beat grid restarts where the stop ends
+        assert_timing(&map, &[(0.0, 120.0), (1000.0, 120.0)]);
+    }
+
+    #[test]
+    fn delays_pause_before_the_beat() {
+        let map = single("#BPMS:0=120;\n#DELAYS:1=0.5;", "1000\n0100\n0010\n0001");
+        assert_objects(
+            &map,
+            &[
+                (0, 0.0, None),
+                (1, 1000.0, None),
+                (2, 1500.0, None),
+                (3, 2000.0, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn offset_is_negated() {
+        let notes = "0000\n1000\n0000\n0000";
+        let late = single("#OFFSET:-0.1;\n#BPMS:0=120;", notes);
+        assert_objects(&late, &[(0, 600.0, None)]);
+        assert_eq!(late.timing[0].time, 100.0);
+
+        let early = single("#OFFSET:0.05;\n#BPMS:0=120;", notes);
+        assert_objects(&early, &[(0, 450.0, None)]);
+    }
+
+    #[test]
+    fn row_subdivisions() {
+        let twelfths = ["0000"; 12]
+            .join("\n")
+            .replacen("0000\n0000", "0000\n0100", 1);
+        let sixteenths = ["0000"; 16]
+            .join("\n")
+            .replacen("0000\n0000", "0000\n0010", 1);
+        let map = single(
+            "#BPMS:0=120;",
+            &format!("1000\n0000\n0000\n0000\n,\n{twelfths}\n,\n{sixteenths}"),
+        );
+        // 2000ms per measure
+        assert_objects(
+            &map,
+            &[
+                (0, 0.0, None),
+                (1, 2000.0 + 2000.0 / 12.0, None),
+                (2, 4000.0 + 2000.0 / 16.0, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn holds_and_rolls() {
+        let map = single("#BPMS:0=120;", "2400\n0000\n3M00\n0300");
+        assert_objects(&map, &[(0, 0.0, Some(1000.0)), (1, 0.0, Some(1500.0))]);
+    }
+
+    #[test]
+    fn hold_without_tail() {
+        let err = ZuchsyaMap::from_stepmania(&sm("#BPMS:0=120;", "dance-single", "2000\n0000"))
+            .unwrap_err();
+        assert!(matches!(err, ConvertError::Parse { .. }), "{err}");
+    }
+
+    #[test]
+    fn dance_double_is_8k() {
+        let maps = ZuchsyaMap::from_stepmania(&sm(
+            "#BPMS:0=120;",
+            "dance-double",
+            "10000001\n00000000\n00000000\n00000000",
+        ))
+        .unwrap();
+        assert_eq!(maps[0].difficulty.keys, 8);
+        assert_eq!(maps[0].metadata.difficulty_name, "Double Hard 9");
+        assert_objects(&maps[0], &[(0, 0.0, None), (7, 0.0, None)]);
+    }
+
+    #[test]
+    fn every_supported_chart_is_converted() {
+        let content = format!(
+            "{}#NOTES:\n     pump-single:\n     :\n     Hard:\n     9:\n     0,0,0,0,0:\n\
+             10000\n00000\n00000\n00000\n;\n\
+             #NOTES:\n     dance-double:\n     :\n     Easy:\n     3:\n     0,0,0,0,0:\n\
+             00000000\n00000000\n00000000\n00000001\n;\n",
+            sm("#BPMS:0=120;", "dance-single", "1000\n0000\n0000\n0000")
+        );
+        let maps = ZuchsyaMap::from_stepmania(&content).unwrap();
+        let charts: Vec<_> = maps
+            .iter()
+            .map(|map| (map.difficulty.keys, map.metadata.difficulty_name.as_str()))
+            .collect();
+        assert_eq!(charts, [(4, "Hard 9"), (8, "Double Easy 3")]);
+        assert_objects(&maps[1], &[(7, 1500.0, None)]);
+    }
+
+    #[test]
+    fn ssc_per_chart_timing() {
+        let content = "\
+#VERSION:0.83;
+#TITLE:Test;
+#OFFSET:0;
+#BPMS:0=120;
+#NOTEDATA:;
+#STEPSTYPE:dance-single;
+#DIFFICULTY:Easy;
+#METER:2;
+#NOTES:
+0000
+1000
+0000
+0000
+;
+#NOTEDATA:;
+#STEPSTYPE:dance-single;
+#DIFFICULTY:Hard;
+#METER:8;
+#OFFSET:-0.1;
+#BPMS:0=240;
+#STOPS:1=0.25;
+#NOTES:
+0000
+1000
+0100
+0000
+;
+";
+        let maps = ZuchsyaMap::from_stepmania(content).unwrap();
+        assert_eq!(maps.len(), 2);
+        assert_objects(&maps[0], &[(0, 500.0, None)]);
+        assert_timing(&maps[0], &[(0.0, 120.0)]);
+        assert_objects(&maps[1], &[(0, 350.0, None), (1, 850.0, None)]);
+        assert_timing(&maps[1], &[(100.0, 240.0), (600.0, 240.0)]);
+    }
+
+    #[test]
+    fn unsupported_charts_only() {
+        let err =
+            ZuchsyaMap::from_stepmania(&sm("#BPMS:0=120;", "pump-single", "10000")).unwrap_err();
+        assert!(matches!(err, ConvertError::UnsupportedMode(_)), "{err}");
+    }
+}
@@ -0,0 +1,203 @@
+//! Beatmap identity - content hash and set ID
+//!
+//! The content hash identifies one version of a chart: it only covers gameplay data
+//! (difficulty, timing, scroll velocities, hit objects), so metadata edits,
+//! comments and YAML formatting don't change it. The set ID groups the
+//! difficulties of one song.
+
+use crate::ZuchsyaMap;
+
+/// Prefix of the hashed data, changed whenever the hashed layout changes
+const CONTENT_HASH_TAG: &[u8] = b"zuchsya-content-v1";
+
+impl ZuchsyaMap {
+    /// Canonical hash of the gameplay data (hex)
+    ///
+    /// Lists are hashed in a canonical order (time first, then every other field),
+    /// so reordering the file doesn't change it.
+    pub fn content_hash(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(CONTENT_HASH_TAG);
+
+        hasher.update(&[self.difficulty.keys]);
+        hasher.update(&canonical_f64(self.difficulty.od as f64));
+        hasher.update(&canonical_f64(self.difficulty.hp as f64));
+
+        let mut timing: Vec<_> = self.timing.iter().collect();
+        timing.sort_by(|a, b| {
+            a.time
+                .total_cmp(&b.time)
+                .then(a.bpm.total_cmp(&b.bpm))
+                .then(a.signature.cmp(&b.signature))
+        });
+        hasher.update(&(timing.len() as u64).to_le_bytes());
+        for point in timing {
+            hasher.update(&canonical_f64(point.time));
+            hasher.update(&canonical_f64(point.bpm));
+            hasher.update(&[point.signature]);
+        }
+
+        let mut scroll_velocities: Vec<_> = self.scroll_velocities.iter().collect();
+        scroll_velocities.sort_by(|a, b| {
+            a.time
+                .total_cmp(&b.time)
+                .then(a.multiplier.total_cmp(&b.multiplier))
+        });
+        hasher.update(&(scroll_velocities.len() as u64).to_le_bytes());
+        for sv in scroll_velocities {
+            hasher.update(&canonical_f64(sv.time));
+            hasher.update(&canonical_f64(sv.multiplier));
+        }
+
+        let mut hit_objects: Vec<_> = self.hit_objects.iter().collect();
+        hit_objects.sort_by(|a, b| {
+            a.time
+                .total_cmp(&b.time)
+                .then(a.lane.cmp(&b.lane))
+                .then(a.get_duration().total_cmp(&b.get_duration()))
+        });
+        hasher.update(&(hit_objects.len() as u64).to_le_bytes());
+        for obj in hit_objects {
+            hasher.update(&canonical_f64(obj.time));
+            hasher.update(&[obj.lane]);
+            match obj.duration {
+                Some(duration) => {
+                    hasher.update(&[1]);
+                    hasher.update(&canonical_f64(duration));
+                }
+                None => {
+                    hasher.update(&[0]);
+                }
+            }
+        }
+
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// ID of the set this difficulty belongs to
+    ///
+    /// Uses `metadata.set_id` if set, otherwise derives one from artist, title and
+    /// creator so difficulties of the same song still group together.
+    pub fn set_id(&self) -> String {
+        if let Some(set_id) = &self.metadata.set_id {
+            return set_id.clone();
+        }
+
+        let metadata = &self.metadata;
+        let mut hasher = blake3::Hasher::new();
+        for field in [&metadata.artist, &metadata.title, &metadata.creator] {
+            hasher.update(&(field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        let hash = hasher.finalize().to_hex();
+        hash[..16].to_string()
+    }
+}
+
+/// Little-endian bytes of a float, with -0.0 hashed like 0.0
+fn canonical_f64(value: f64) -> [u8; 8] {
+    let value = if value == 0.0 { 0.0 } else { value };
+    value.to_bits().to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "\
+version: 1
+metadata:
+  title: Identity
+  artist: Zuchsya
+  creator: Zuchsya
+  difficulty_name: Normal
+audio:
+  file: audio.mp3
+difficulty:
+  keys: 4
+  od: 8.0
+  hp: 7.0
+timing:
+  - time: 0
+    bpm: 120.0
+  - time: 2000
+    bpm: 180.0
+    signature: 3
+  - time: 2000
+    bpm: 90.0
+scroll_velocities:
+  - time: 1000
+    multiplier: 0.5
+  - time: 1000
+    multiplier: 2.0
+hit_objects:
+  - time: 500
+    lane: 0
+  - time: 500
+    lane: 2
+  - time: 1000
+    lane: 1
+    duration: 500
+";
+
+    fn hash(yaml: &str) -> String {
+        ZuchsyaMap::from_yaml(yaml).unwrap().content_hash()
+    }
+
+    #[test]
+    fn ignores_formatting_and_metadata() {
+        // Comments, flow style, key order, quoting and metadata differ
+        let reformatted = "\
+# Same chart, written differently
+hit_objects: [{lane: 0, time: 500}, {lane: 2, time: 500.0}, {duration: 500, lane: 1, time: 1000}]
+scroll_velocities:
+- {multiplier: 0.5, time: 1000}
+- {multiplier: 2, time: 1000}
+timing:
+- {bpm: 120, time: 0}
+- {bpm: 180, time: 2000, signature: 3}
+- {bpm: 90, time: 2000}   # trailing comment
+difficulty: {hp: 7, od: 8, keys: 4}
+audio: {file: \"other.ogg\"}
+metadata: {difficulty_name: Hard, creator: Someone, artist: Zuchsya, title: \"Renamed\"}
+version: 1
+";
+        assert_eq!(hash(MAP), hash(reformatted));
+    }
+
+    #[test]
+    fn ignores_list_order() {
+        let mut map = ZuchsyaMap::from_yaml(MAP).unwrap();
+        let expected = map.content_hash();
+
+        // Points at the same time are only told apart by their other fields
+        map.timing.reverse();
+        map.scroll_velocities.reverse();
+        map.hit_objects.reverse();
+        assert_eq!(map.content_hash(), expected);
+    }
+
+    #[test]
+    fn changes_with_gameplay_data() {
+        let original = ZuchsyaMap::from_yaml(MAP).unwrap();
+        let expected = original.content_hash();
+
+        let changes: [fn(&mut ZuchsyaMap); 10] = [
+            |map| map.difficulty.keys = 5,
+            |map| map.difficulty.od = 8.5,
+            |map| map.difficulty.hp = 6.0,
+            |map| map.timing[1].bpm = 181.0,
+            |map| map.timing[1].signature = 4,
+            |map| map.scroll_velocities[0].multiplier = 0.75,
+            |map| map.scroll_velocities.truncate(1),
+            |map| map.hit_objects[0].time = 501.0,
+            |map| map.hit_objects[1].lane = 3,
+            |map| map.hit_objects[2].duration = None,
+        ];
+        for (i, change) in changes.iter().enumerate() {
+            let mut map = original.clone();
+            change(&mut map);
+            assert_ne!(map.content_hash(), expected, "change {i}");
+        }
+    }
+}
@@ -2,21 +2,56 @@
 //!
 //! This crate contains the core types shared across the Zuchsya project:
 //! - Game state
-//! - Beatmap format and types
+//! - Beatmap format and types, with migrations from older format versions
+//! - Beatmap validation
+//! - Beatmap content hashes and set IDs
+//! - Beatmap set archives
 //! - Hit objects (Note, HoldNote)
 //! - Timing points
 //! - Scoring/Judgement types
+//! - Gameplay mods
+//! - Audio offsets and calibration
+//! - Player preferences
+//! - Local score database
+//! - Star rating and pattern analysis
+//! - Replays
+//! - Performance points and player profile
+//! - Conversion from other formats
 
+pub mod analysis;
+pub mod archive;
 pub mod beatmap;
+pub mod convert;
 pub mod hit_object;
+pub mod identity;
+pub mod migration;
+pub mod mods;
+pub mod offset;
+pub mod performance;
+pub mod preferences;
+pub mod replay;
+pub mod score_store;
 pub mod scoring;
 pub mod scroll_velocity;
+pub mod star_rating;
 pub mod state;
 pub mod timing;
+pub mod validation;
 
+pub use analysis::*;
+pub use archive::*;
 pub use beatmap::*;
+pub use convert::{BmsKind, ConvertError, ImportDiagnostic, Imported};
 pub use hit_object::*;
+pub use mods::*;
+pub use offset::*;
+pub use performance::*;
+pub use preferences::*;
+pub use replay::*;
+pub use score_store::*;
 pub use scoring::*;
 pub use scroll_velocity::*;
+pub use star_rating::*;
 pub use state::*;
 pub use timing::*;
+pub use validation::*;
@@ -0,0 +1,59 @@
+//! Beatmap format migrations
+//!
+//! Beatmaps are read as raw YAML first and upgraded one version at a time up to
+//! `FORMAT_VERSION` before being deserialized into `ZuchsyaMap`.
+//!
+//! Changing the format:
+//! 1. Bump `FORMAT_VERSION`
+//! 2. Append a step upgrading the previous version to `MIGRATIONS`
+//! 3. Add a fixture of the previous version to `fixtures/format/`
+
+use serde_yaml::{Mapping, Value};
+
+use crate::{BeatmapError, FORMAT_VERSION};
+
+/// Upgrades a beatmap document by one version, in place
+type Migration = fn(&mut Mapping) -> Result<(), BeatmapError>;
+
+/// Migration steps: `MIGRATIONS[i]` upgrades version `i + 1` to `i + 2`
+const MIGRATIONS: &[Migration] = &[];
+
+// Every version before the current one needs a migration step
+const _: () = assert!(MIGRATIONS.len() as u32 + 1 == FORMAT_VERSION);
+
+/// Format version of a beatmap document
+pub fn format_version(document: &Value) -> Result<u32, BeatmapError> {
+    let version = document
+        .get("version")
+        .ok_or(BeatmapError::MissingVersion)?;
+
+    version
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .filter(|v| *v >= 1)
+        .ok_or_else(|| BeatmapError::InvalidVersion(format!("{:?}", version)))
+}
+
+/// Upgrade a beatmap document to `FORMAT_VERSION`
+///
+/// Returns the upgraded document and the version it was upgraded from.
+pub fn migrate(mut document: Value) -> Result<(Value, u32), BeatmapError> {
+    let original = format_version(&document)?;
+    if original > FORMAT_VERSION {
+        return Err(BeatmapError::UnsupportedVersion {
+            found: original,
+            supported: FORMAT_VERSION,
+        });
+    }
+
+    let Value::Mapping(mapping) = &mut document else {
+        return Err(BeatmapError::NotAMapping);
+    };
+
+    for (step, migration) in MIGRATIONS.iter().enumerate().skip(original as usize - 1) {
+        migration(mapping)?;
+        mapping.insert("version".into(), (step as u32 + 2).into());
+    }
+
+    Ok((document, original))
+}
@@ -0,0 +1,451 @@
+//! Gameplay mods
+//!
+//! Mods are applied on the way from a `ZuchsyaMap` into gameplay: lane mods move
+//! hit objects, difficulty mods change OD/HP, rate mods change the speed of the
+//! whole map. They are recorded as acronyms (e.g. `"HR"`, `"RD:1234"`, `"DT:1.25"`)
+//! in replays and scores.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Difficulty, HitObject, HitResult};
+
+/// Slowest supported rate
+pub const MIN_RATE: f64 = 0.5;
+/// Fastest supported rate
+pub const MAX_RATE: f64 = 2.0;
+
+/// Rates are picked in steps of this size
+pub const RATE_STEP: f64 = 0.05;
+
+/// Rate of plain Half Time
+pub const HALF_TIME_RATE: f64 = 0.75;
+/// Rate of plain Double Time
+pub const DOUBLE_TIME_RATE: f64 = 1.5;
+
+/// OD/HP multiplier of Hard Rock (capped at 10)
+const HARD_ROCK_DIFFICULTY_MULTIPLIER: f32 = 1.4;
+/// OD/HP multiplier of Easy
+const EASY_DIFFICULTY_MULTIPLIER: f32 = 0.5;
+
+/// A gameplay mod
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mod {
+    /// Larger hit windows, slower health drain
+    Easy,
+    /// Can't fail
+    NoFail,
+    /// Half Time (rate < 1) or Double Time (rate > 1)
+    Rate(f64),
+    /// Tighter hit windows, faster health drain
+    HardRock,
+    /// Fail on the first miss
+    SuddenDeath,
+    /// Fail on anything but a Perfect
+    Perfect,
+    /// Lanes flipped left to right
+    Mirror,
+    /// Lanes shuffled, the seed makes the shuffle repeatable
+    Random { seed: u64 },
+    /// Played by the game
+    Autoplay,
+}
+
+impl Mod {
+    /// Short name, e.g. "HR"
+    pub fn acronym(&self) -> &'static str {
+        match self {
+            Self::Easy => "EZ",
+            Self::NoFail => "NF",
+            Self::Rate(rate) if *rate < 1.0 => "HT",
+            Self::Rate(_) => "DT",
+            Self::HardRock => "HR",
+            Self::SuddenDeath => "SD",
+            Self::Perfect => "PF",
+            Self::Mirror => "MR",
+            Self::Random { .. } => "RD",
+            Self::Autoplay => "AT",
+        }
+    }
+
+    /// Multiplier applied to the score
+    pub fn score_multiplier(&self) -> f64 {
+        match self {
+            Self::Easy | Self::NoFail => 0.5,
+            Self::Rate(rate) if *rate < 1.0 => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Display order, and mods with the same group can't be combined
+    fn group(&self) -> u8 {
+        match self {
+            Self::Easy | Self::HardRock => 0,
+            Self::NoFail | Self::SuddenDeath | Self::Perfect => 1,
+            Self::Rate(_) => 2,
+            Self::Mirror | Self::Random { .. } => 3,
+            Self::Autoplay => 4,
+        }
+    }
+
+    /// Same mod, ignoring its settings
+    fn same_kind(&self, other: &Mod) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl fmt::Display for Mod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rate(rate) if *rate == HALF_TIME_RATE || *rate == DOUBLE_TIME_RATE => {
+                write!(f, "{}", self.acronym())
+            }
+            Self::Rate(rate) => write!(f, "{}:{}", self.acronym(), rate),
+            Self::Random { seed } => write!(f, "{}:{}", self.acronym(), seed),
+            _ => write!(f, "{}", self.acronym()),
+        }
+    }
+}
+
+impl FromStr for Mod {
+    type Err = ModError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (acronym, setting) = match s.split_once(':') {
+            Some((acronym, setting)) => (acronym, Some(setting)),
+            None => (s, None),
+        };
+        let invalid = || ModError::InvalidSetting(s.to_string());
+
+        // HT rates are below 1.0, DT rates above it
+        let rate = |default: f64| -> Result<f64, ModError> {
+            let rate = setting.map_or(Ok(default), |r| r.parse().map_err(|_| invalid()))?;
+            let same_side = rate != 1.0 && (rate < 1.0) == (default < 1.0);
+            if same_side && (MIN_RATE..=MAX_RATE).contains(&rate) {
+                Ok(rate)
+            } else {
+                Err(invalid())
+            }
+        };
+
+        let m = match acronym {
+            "EZ" => Self::Easy,
+            "NF" => Self::NoFail,
+            "HT" => Self::Rate(rate(HALF_TIME_RATE)?),
+            "DT" => Self::Rate(rate(DOUBLE_TIME_RATE)?),
+            "HR" => Self::HardRock,
+            "SD" => Self::SuddenDeath,
+            "PF" => Self::Perfect,
+            "MR" => Self::Mirror,
+            "RD" => Self::Random {
+                seed: setting
+                    .ok_or_else(invalid)?
+                    .parse()
+                    .map_err(|_| invalid())?,
+            },
+            "AT" => Self::Autoplay,
+            _ => return Err(ModError::Unknown(s.to_string())),
+        };
+        if setting.is_some() && !matches!(m, Self::Rate(_) | Self::Random { .. }) {
+            return Err(invalid());
+        }
+        Ok(m)
+    }
+}
+
+/// A combination of mods, at most one of each kind and none conflicting
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mods(Vec<Mod>);
+
+impl Mods {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse recorded acronyms (replays, scores)
+    pub fn from_acronyms(acronyms: &[String]) -> Result<Self, ModError> {
+        let mut mods = Self::new();
+        for acronym in acronyms {
+            mods.insert(acronym.parse()?);
+        }
+        Ok(mods)
+    }
+
+    /// Acronyms to record with a replay or score
+    pub fn to_acronyms(&self) -> Vec<String> {
+        self.0.iter().map(|m| m.to_string()).collect()
+    }
+
+    /// Active mods in display order
+    pub fn iter(&self) -> impl Iterator<Item = &Mod> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add a mod, replacing the same kind of mod and any it conflicts with
+    pub fn insert(&mut self, m: Mod) {
+        self.0.retain(|other| other.group() != m.group());
+        self.0.push(m);
+        self.0.sort_by_key(Mod::group);
+    }
+
+    /// Remove a mod of the same kind (settings are ignored)
+    pub fn remove(&mut self, m: &Mod) {
+        self.0.retain(|other| !other.same_kind(m));
+    }
+
+    /// Check for a mod of the same kind (settings are ignored)
+    pub fn contains(&self, m: &Mod) -> bool {
+        self.0.iter().any(|other| other.same_kind(m))
+    }
+
+    /// Add the mod, or remove it if it is already active with the same settings
+    pub fn toggle(&mut self, m: Mod) {
+        if self.0.contains(&m) {
+            self.0.retain(|other| *other != m);
+        } else {
+            self.insert(m);
+        }
+    }
+
+    /// Playback rate (1.0 without a rate mod)
+    pub fn rate(&self) -> f64 {
+        self.0
+            .iter()
+            .find_map(|m| match m {
+                Mod::Rate(rate) => Some(*rate),
+                _ => None,
+            })
+            .unwrap_or(1.0)
+    }
+
+    /// Set the rate (snapped to `RATE_STEP`), 1.0 removes the rate mod
+    pub fn set_rate(&mut self, rate: f64) {
+        let rate = ((rate / RATE_STEP).round() * RATE_STEP).clamp(MIN_RATE, MAX_RATE);
+        // Round off float error so the recorded rate reads e.g. "1.15"
+        let rate = (rate * 100.0).round() / 100.0;
+        if rate == 1.0 {
+            self.remove(&Mod::Rate(rate));
+        } else {
+            self.insert(Mod::Rate(rate));
+        }
+    }
+
+    /// Multiplier applied to the score
+    pub fn score_multiplier(&self) -> f64 {
+        self.0.iter().map(Mod::score_multiplier).product()
+    }
+
+    pub fn no_fail(&self) -> bool {
+        self.contains(&Mod::NoFail)
+    }
+
+    /// Check if a judgement immediately fails the play (Sudden Death / Perfect)
+    pub fn fails_on(&self, result: HitResult) -> bool {
+        (self.contains(&Mod::SuddenDeath) && result.breaks_combo())
+            || (self.contains(&Mod::Perfect) && result != HitResult::Perfect)
+    }
+
+    /// Difficulty with OD/HP changed by Hard Rock / Easy
+    pub fn apply_difficulty(&self, difficulty: &Difficulty) -> Difficulty {
+        let multiplier = if self.contains(&Mod::HardRock) {
+            HARD_ROCK_DIFFICULTY_MULTIPLIER
+        } else if self.contains(&Mod::Easy) {
+            EASY_DIFFICULTY_MULTIPLIER
+        } else {
+            1.0
+        };
+        Difficulty {
+            keys: difficulty.keys,
+            od: (difficulty.od * multiplier).min(10.0),
+            hp: (difficulty.hp * multiplier).min(10.0),
+        }
+    }
+
+    /// Move hit objects to their lanes under Mirror / Random
+    ///
+    /// Objects stay in time order. Times are not changed - rate mods change the
+    /// speed of the clock instead.
+    pub fn apply_lanes(&self, objects: &mut [HitObject], keys: u8) {
+        let lanes = if self.contains(&Mod::Mirror) {
+            (0..keys).rev().collect()
+        } else if let Some(seed) = self.0.iter().find_map(|m| match m {
+            Mod::Random { seed } => Some(*seed),
+            _ => None,
+        }) {
+            shuffled_lanes(keys, seed)
+        } else {
+            return;
+        };
+
+        for object in objects {
+            if let Some(&lane) = lanes.get(object.lane as usize) {
+                object.lane = lane;
+            }
+        }
+    }
+
+    /// Human readable list, e.g. "HR DT(1.25x)", empty without mods
+    pub fn label(&self) -> String {
+        let labels: Vec<String> = self
+            .0
+            .iter()
+            .map(|m| match m {
+                Mod::Rate(rate) => format!("{}({}x)", m.acronym(), rate),
+                _ => m.acronym().to_string(),
+            })
+            .collect();
+        labels.join(" ")
+    }
+}
+
+/// Permutation of `0..keys` for a Random seed (Fisher-Yates over splitmix64)
+fn shuffled_lanes(keys: u8, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    let mut lanes: Vec<u8> = (0..keys).collect();
+    for i in (1..lanes.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        lanes.swap(i, j);
+    }
+    lanes
+}
+
+/// Mod parsing errors
+#[derive(Debug, thiserror::Error)]
+pub enum ModError {
+    #[error("Unknown mod: {0}")]
+    Unknown(String),
+    #[error("Invalid mod setting: {0}")]
+    InvalidSetting(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mods(acronyms: &[&str]) -> Mods {
+        let acronyms: Vec<String> = acronyms.iter().map(|a| a.to_string()).collect();
+        Mods::from_acronyms(&acronyms).unwrap()
+    }
+
+    #[test]
+    fn acronyms_round_trip() {
+        for acronym in [
+            "EZ", "NF", "HT", "HT:0.8", "DT", "DT:1.25", "HR", "SD", "PF", "MR", "RD:1234", "AT",
+        ] {
+            let m: Mod = acronym.parse().unwrap();
+            assert_eq!(m.to_string(), acronym);
+        }
+
+        // Plain HT/DT rates are written without a setting
+        assert_eq!("DT:1.5".parse::<Mod>().unwrap().to_string(), "DT");
+        assert_eq!("HT:0.75".parse::<Mod>().unwrap(), Mod::Rate(HALF_TIME_RATE));
+    }
+
+    #[test]
+    fn invalid_acronyms() {
+        assert!(matches!("XX".parse::<Mod>(), Err(ModError::Unknown(_))));
+        // Rates on the wrong side of 1.0 would read back as the other mod
+        let wrong_side = ["HT:1.5", "DT:0.75", "HT:1", "DT:1"];
+        let out_of_range = ["HT:0.4", "DT:2.5", "DT:fast"];
+        let bad_setting = ["RD", "RD:x", "HR:1"];
+        for acronym in wrong_side
+            .into_iter()
+            .chain(out_of_range)
+            .chain(bad_setting)
+        {
+            assert!(
+                matches!(acronym.parse::<Mod>(), Err(ModError::InvalidSetting(_))),
+                "{acronym}"
+            );
+        }
+    }
+
+    #[test]
+    fn same_group_mods_replace_each_other() {
+        let mut active = mods(&["HR", "SD", "MR"]);
+        active.insert(Mod::Easy);
+        active.insert(Mod::Perfect);
+        active.insert(Mod::Random { seed: 7 });
+        assert_eq!(active.to_acronyms(), ["EZ", "PF", "RD:7"]);
+
+        // Recorded acronyms go through the same rules
+        assert_eq!(mods(&["NF", "SD"]).to_acronyms(), ["SD"]);
+        assert_eq!(mods(&["HT", "DT:1.2"]).to_acronyms(), ["DT:1.2"]);
+    }
+
+    #[test]
+    fn display_order() {
+        assert_eq!(
+            mods(&["AT", "RD:1", "DT", "PF", "HR"]).to_acronyms(),
+            ["HR", "PF", "DT", "RD:1", "AT"]
+        );
+    }
+
+    #[test]
+    fn toggle() {
+        let mut active = Mods::new();
+        active.toggle(Mod::HardRock);
+        assert!(active.contains(&Mod::HardRock));
+        active.toggle(Mod::HardRock);
+        assert!(active.is_empty());
+    }
+
+    #[test]
+    fn set_rate_snaps_to_steps() {
+        let mut active = Mods::new();
+        for (rate, expected) in [
+            (1.13, 1.15),
+            (1.12, 1.1),
+            (0.81, 0.8),
+            (0.1, MIN_RATE),
+            (3.0, MAX_RATE),
+        ] {
+            active.set_rate(rate);
+            assert_eq!(active.rate(), expected, "{rate}");
+        }
+
+        active.set_rate(1.15);
+        assert_eq!(active.to_acronyms(), ["DT:1.15"]);
+
+        // Snapping to 1.0 removes the rate mod
+        active.set_rate(1.02);
+        assert!(active.is_empty());
+        assert_eq!(active.rate(), 1.0);
+    }
+
+    #[test]
+    fn mirror_flips_lanes() {
+        let mut objects: Vec<HitObject> = (0..7).map(|lane| HitObject::note(lane, 0.0)).collect();
+        mods(&["MR"]).apply_lanes(&mut objects, 7);
+        let lanes: Vec<u8> = objects.iter().map(|obj| obj.lane).collect();
+        assert_eq!(lanes, [6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn random_is_stable_per_seed() {
+        // Replays are played back with the recorded seed, so the shuffle for a seed
+        // must never change
+        assert_eq!(shuffled_lanes(4, 1234), [1, 0, 2, 3]);
+        assert_eq!(shuffled_lanes(7, 1234), [5, 4, 0, 3, 6, 2, 1]);
+
+        for keys in 1..=10 {
+            let mut lanes = shuffled_lanes(keys, 42);
+            assert_eq!(lanes, shuffled_lanes(keys, 42));
+            lanes.sort();
+            assert_eq!(lanes, (0..keys).collect::<Vec<u8>>());
+        }
+        assert_ne!(shuffled_lanes(7, 1), shuffled_lanes(7, 2));
+    }
+}
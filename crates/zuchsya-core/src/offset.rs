@@ -0,0 +1,91 @@
+//! Audio offsets - compensate for the delay between the audio clock and what is heard
+//!
+//! Offsets are in milliseconds, positive when the music is heard late. The global
+//! offset applies to every map, and a beatmap's local offset is added on top.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Taps trimmed from each end before averaging a calibration (fraction of all taps)
+const CALIBRATION_TRIM: f64 = 0.1;
+
+/// Global and per-beatmap offsets (offsets.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioOffsets {
+    /// Offset of every map (ms)
+    #[serde(default)]
+    pub global_ms: f64,
+    /// Additional offset of single maps (ms), by content hash
+    #[serde(default)]
+    pub local_ms: BTreeMap<String, f64>,
+}
+
+impl AudioOffsets {
+    /// Load offsets from file
+    pub fn load(path: &Path) -> Result<Self, OffsetError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save offsets to file
+    pub fn save(&self, path: &Path) -> Result<(), OffsetError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Local offset of a map (0 if it has none)
+    pub fn local(&self, map_hash: &str) -> f64 {
+        self.local_ms.get(map_hash).copied().unwrap_or(0.0)
+    }
+
+    /// Set the local offset of a map, 0 removes it
+    pub fn set_local(&mut self, map_hash: &str, offset_ms: f64) {
+        if offset_ms == 0.0 {
+            self.local_ms.remove(map_hash);
+        } else {
+            self.local_ms.insert(map_hash.to_string(), offset_ms);
+        }
+    }
+
+    /// Offset to apply when playing a map
+    pub fn total(&self, map_hash: &str) -> f64 {
+        self.global_ms + self.local(map_hash)
+    }
+}
+
+/// Offset of a tap from the nearest beat of a metronome starting at 0
+pub fn tap_offset(time_ms: f64, beat_interval_ms: f64) -> f64 {
+    let phase = time_ms.rem_euclid(beat_interval_ms);
+    if phase > beat_interval_ms / 2.0 {
+        phase - beat_interval_ms
+    } else {
+        phase
+    }
+}
+
+/// Average offset of calibration taps, ignoring the furthest off ones
+pub fn mean_tap_offset(offsets: &[f64]) -> Option<f64> {
+    if offsets.is_empty() {
+        return None;
+    }
+    let mut sorted = offsets.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let trim = (sorted.len() as f64 * CALIBRATION_TRIM) as usize;
+    let kept = &sorted[trim..sorted.len() - trim];
+    Some(kept.iter().sum::<f64>() / kept.len() as f64)
+}
+
+/// Offset loading/saving errors
+#[derive(Debug, thiserror::Error)]
+pub enum OffsetError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+}
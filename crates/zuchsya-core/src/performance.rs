@@ -0,0 +1,208 @@
+//! Performance points - how much a score is worth
+//!
+//! Follows osu!mania: the map's star rating is turned into a difficulty value,
+//! which is scaled by accuracy (nothing below 80%) and a small length bonus. A
+//! player's total weights their best score on each map, best first.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ScoreSummary;
+
+/// Overall scale of performance values
+const PERFORMANCE_BASE_MULTIPLIER: f64 = 8.0;
+
+/// Judgements after which the length bonus is at its maximum
+const LENGTH_BONUS_JUDGEMENTS: f64 = 1500.0;
+
+/// Weight falloff of each next-best score in the profile total
+const PROFILE_DECAY_WEIGHT: f64 = 0.95;
+
+/// Performance value of a score on a map with the given star rating
+///
+/// `mods` are the acronyms the score was set with.
+pub fn performance(star_rating: f64, score: &ScoreSummary, mods: &[String]) -> f64 {
+    let judgements = score.perfect + score.great + score.good + score.ok + score.meh + score.miss;
+    if judgements == 0 {
+        return 0.0;
+    }
+    let judgements = judgements as f64;
+
+    // Perfects are worth slightly more than greats here, unlike in score accuracy
+    let accuracy = (score.perfect as f64 * 320.0
+        + score.great as f64 * 300.0
+        + score.good as f64 * 200.0
+        + score.ok as f64 * 100.0
+        + score.meh as f64 * 50.0)
+        / (judgements * 320.0);
+
+    let difficulty_value = (star_rating - 0.15).max(0.05).powf(2.2)
+        // From 80% accuracy, each additional 1% is worth 1/20th of the value
+        * (5.0 * accuracy - 4.0).max(0.0)
+        * (1.0 + 0.1 * (judgements / LENGTH_BONUS_JUDGEMENTS).min(1.0));
+
+    let mut multiplier = PERFORMANCE_BASE_MULTIPLIER;
+    if mods.iter().any(|m| m == "NF") {
+        multiplier *= 0.75;
+    }
+    if mods.iter().any(|m| m == "EZ") {
+        multiplier *= 0.5;
+    }
+
+    difficulty_value * multiplier
+}
+
+/// Player performance across maps (profile.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    /// Best performance on each map, by content hash
+    #[serde(default)]
+    pub best_performance: BTreeMap<String, f64>,
+    /// Number of finished plays
+    #[serde(default)]
+    pub play_count: u32,
+}
+
+impl PlayerProfile {
+    /// Load profile from file
+    pub fn load(path: &Path) -> Result<Self, ProfileError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save profile to file
+    pub fn save(&self, path: &Path) -> Result<(), ProfileError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record a finished play, returns true if it is the best on its map
+    pub fn record(&mut self, map_hash: &str, performance: f64) -> bool {
+        self.play_count += 1;
+        match self.best_performance.get_mut(map_hash) {
+            Some(best) if *best >= performance => false,
+            Some(best) => {
+                *best = performance;
+                true
+            }
+            None => {
+                self.best_performance
+                    .insert(map_hash.to_string(), performance);
+                true
+            }
+        }
+    }
+
+    /// Weighted total: best map counts fully, each next one 5% less
+    pub fn total_performance(&self) -> f64 {
+        let mut values: Vec<f64> = self.best_performance.values().copied().collect();
+        values.sort_by(|a, b| b.total_cmp(a));
+        let mut weight = 1.0;
+        let mut total = 0.0;
+        for value in values {
+            total += value * weight;
+            weight *= PROFILE_DECAY_WEIGHT;
+        }
+        total
+    }
+}
+
+/// Profile loading/saving errors
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(perfect: u32, miss: u32) -> ScoreSummary {
+        ScoreSummary {
+            perfect,
+            miss,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn full_perfect_score() {
+        // (2.15 - 0.15)^2.2 * 1.0 accuracy * 1.1 length bonus * 8
+        let expected = 2.0f64.powf(2.2) * 1.1 * 8.0;
+        assert_close(performance(2.15, &score(1500, 0), &[]), expected);
+    }
+
+    #[test]
+    fn nothing_below_80_percent_accuracy() {
+        // Exactly 80%, then 50%
+        assert_eq!(performance(5.0, &score(4, 1), &[]), 0.0);
+        assert_eq!(performance(5.0, &score(1, 1), &[]), 0.0);
+
+        // 90% is halfway between 80% and 100%
+        let full = performance(5.0, &score(1500, 0), &[]);
+        let ninety = performance(5.0, &score(1350, 150), &[]);
+        assert_close(ninety, full * 0.5);
+    }
+
+    #[test]
+    fn length_bonus() {
+        let value = |judgements| performance(3.0, &score(judgements, 0), &[]);
+        // Up to 10% more, reached at 1500 judgements
+        assert_close(value(750) / value(1500), 1.05 / 1.1);
+        assert_close(value(3000), value(1500));
+    }
+
+    #[test]
+    fn mod_multipliers() {
+        let mods = |mods: &[&str]| -> Vec<String> { mods.iter().map(|m| m.to_string()).collect() };
+        let value = |m: &[&str]| performance(4.0, &score(1000, 0), &mods(m));
+        let nomod = value(&[]);
+        assert_close(value(&["NF"]), nomod * 0.75);
+        assert_close(value(&["EZ"]), nomod * 0.5);
+        assert_close(value(&["NF", "EZ"]), nomod * 0.375);
+        assert_close(value(&["HR"]), nomod);
+    }
+
+    #[test]
+    fn no_judgements() {
+        assert_eq!(performance(5.0, &ScoreSummary::default(), &[]), 0.0);
+    }
+
+    #[test]
+    fn profile_weights_best_first() {
+        let mut profile = PlayerProfile::default();
+        profile.record("a", 100.0);
+        profile.record("b", 50.0);
+        profile.record("c", 200.0);
+        assert_close(
+            profile.total_performance(),
+            200.0 + 100.0 * 0.95 + 50.0 * 0.95 * 0.95,
+        );
+    }
+
+    #[test]
+    fn record_detects_personal_best() {
+        let mut profile = PlayerProfile::default();
+        assert!(profile.record("map", 100.0));
+        assert!(!profile.record("map", 80.0));
+        assert!(!profile.record("map", 100.0));
+        assert!(profile.record("map", 120.0));
+        assert_eq!(profile.best_performance["map"], 120.0);
+        assert_eq!(profile.play_count, 4);
+    }
+}
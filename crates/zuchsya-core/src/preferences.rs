@@ -0,0 +1,39 @@
+//! Player preferences that are kept between sessions
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Saved player preferences (preferences.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Preferences {
+    /// Scroll at constant speed, ignoring beatmaps' SV changes
+    #[serde(default)]
+    pub ignore_scroll_velocity: bool,
+}
+
+impl Preferences {
+    /// Load preferences from file
+    pub fn load(path: &Path) -> Result<Self, PreferencesError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save preferences to file
+    pub fn save(&self, path: &Path) -> Result<(), PreferencesError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Preferences loading/saving errors
+#[derive(Debug, thiserror::Error)]
+pub enum PreferencesError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+}
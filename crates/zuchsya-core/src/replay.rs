@@ -0,0 +1,102 @@
+//! Replay format - recorded key input for a play
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::ScoreSummary;
+
+/// Current replay format version
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Recorded play (.zrp file, JSON)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    /// Format version
+    pub version: u32,
+    /// Content hash of the beatmap that was played (`ZuchsyaMap::content_hash`)
+    pub map_hash: String,
+    /// Number of keys (columns)
+    pub key_count: u8,
+    /// Active mod acronyms
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mods: Vec<String>,
+    /// Final score of the play
+    pub score: ScoreSummary,
+    /// Performance value of the score (see `performance`)
+    #[serde(default)]
+    pub performance: f64,
+    /// Unix timestamp (seconds) when the play finished
+    pub timestamp: u64,
+    /// Key state changes in time order
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Key state at a point in time
+///
+/// A frame is only recorded when the pressed keys change.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// Game time in milliseconds
+    pub time: f64,
+    /// Pressed keys as a bitmask (bit n = column n)
+    pub keys: u16,
+}
+
+impl ReplayFrame {
+    pub fn new(time: f64, keys: u16) -> Self {
+        Self { time, keys }
+    }
+
+    /// Check if a column is pressed in this frame
+    pub fn is_pressed(&self, column: usize) -> bool {
+        column < 16 && self.keys & (1 << column) != 0
+    }
+}
+
+impl Replay {
+    /// Load replay from file
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    /// Parse replay from JSON string
+    pub fn from_json(json: &str) -> Result<Self, ReplayError> {
+        let replay: Self = serde_json::from_str(json)?;
+        if replay.version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    /// Serialize replay to JSON string
+    pub fn to_json(&self) -> Result<String, ReplayError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Save replay to file
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Default file name for this replay
+    pub fn file_name(&self) -> String {
+        let hash: String = self.map_hash.chars().take(12).collect();
+        format!("{}-{}.zrp", hash, self.timestamp)
+    }
+}
+
+/// Replay loading/saving errors
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported replay version: {0}")]
+    UnsupportedVersion(u32),
+}
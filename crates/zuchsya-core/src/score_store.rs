@@ -0,0 +1,215 @@
+//! Local score database
+//!
+//! Scores are appended to a JSON lines file (one record per line), so saving a play
+//! never rewrites earlier scores. Leaderboards are built by beatmap content hash.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ScoreRank, ScoreSummary};
+
+/// One finished play
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreRecord {
+    /// Content hash of the beatmap (`ZuchsyaMap::content_hash`)
+    pub map_hash: String,
+    pub score: ScoreSummary,
+    pub rank: ScoreRank,
+    /// Active mod acronyms
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mods: Vec<String>,
+    /// Playback rate (1.0 = normal speed)
+    #[serde(default = "default_rate")]
+    pub rate: f64,
+    /// Performance value (see `performance`)
+    #[serde(default)]
+    pub performance: f64,
+    /// Unix timestamp (seconds) when the play finished
+    pub timestamp: u64,
+    /// Replay file name, relative to the replay folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<String>,
+}
+
+fn default_rate() -> f64 {
+    1.0
+}
+
+/// Scores of all maps, backed by a JSON lines file
+#[derive(Debug, Clone, Default)]
+pub struct ScoreStore {
+    path: PathBuf,
+    records: Vec<ScoreRecord>,
+    /// Lines that couldn't be parsed when opening (e.g. cut off by a crash)
+    pub skipped_lines: usize,
+}
+
+impl ScoreStore {
+    /// Open the store at `path`, empty if the file doesn't exist yet
+    pub fn open(path: &Path) -> Result<Self, ScoreStoreError> {
+        let mut store = Self {
+            path: path.to_path_buf(),
+            ..Default::default()
+        };
+        if !path.exists() {
+            return Ok(store);
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(record) => store.records.push(record),
+                Err(_) => store.skipped_lines += 1,
+            }
+        }
+        Ok(store)
+    }
+
+    /// Append a score to the store and its file
+    pub fn add(&mut self, record: ScoreRecord) -> Result<(), ScoreStoreError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Every score in the store, oldest first
+    pub fn records(&self) -> &[ScoreRecord] {
+        &self.records
+    }
+
+    /// Scores on a map, best first (ties go to the earlier score)
+    pub fn leaderboard(&self, map_hash: &str) -> Vec<&ScoreRecord> {
+        let mut scores: Vec<&ScoreRecord> = self
+            .records
+            .iter()
+            .filter(|record| record.map_hash == map_hash)
+            .collect();
+        scores.sort_by(|a, b| {
+            b.score
+                .score
+                .cmp(&a.score.score)
+                .then(a.timestamp.cmp(&b.timestamp))
+        });
+        scores
+    }
+
+    /// Best score on a map
+    pub fn personal_best(&self, map_hash: &str) -> Option<&ScoreRecord> {
+        self.leaderboard(map_hash).into_iter().next()
+    }
+}
+
+/// Score store errors
+#[derive(Debug, thiserror::Error)]
+pub enum ScoreStoreError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh store file in the temp folder
+    fn store_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zuchsya-scores-{}", std::process::id()));
+        let path = dir.join(format!("{}.jsonl", name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn record(map_hash: &str, score: i64, timestamp: u64) -> ScoreRecord {
+        ScoreRecord {
+            map_hash: map_hash.to_string(),
+            score: ScoreSummary {
+                score,
+                ..Default::default()
+            },
+            rank: ScoreRank::A,
+            mods: Vec::new(),
+            rate: 1.0,
+            performance: 0.0,
+            timestamp,
+            replay: None,
+        }
+    }
+
+    fn timestamps(scores: &[&ScoreRecord]) -> Vec<u64> {
+        scores.iter().map(|record| record.timestamp).collect()
+    }
+
+    #[test]
+    fn leaderboard_is_best_first_with_earlier_ties() {
+        let path = store_path("leaderboard");
+        let mut store = ScoreStore::open(&path).unwrap();
+        store.add(record("map", 800_000, 1)).unwrap();
+        store.add(record("map", 950_000, 2)).unwrap();
+        store.add(record("other", 999_999, 3)).unwrap();
+        store.add(record("map", 950_000, 4)).unwrap();
+
+        assert_eq!(timestamps(&store.leaderboard("map")), [2, 4, 1]);
+        assert_eq!(store.personal_best("map").unwrap().timestamp, 2);
+        assert!(store.personal_best("missing").is_none());
+
+        // Scores survive reopening, in the order they were set
+        let reopened = ScoreStore::open(&path).unwrap();
+        assert_eq!(reopened.records(), store.records());
+        assert_eq!(reopened.skipped_lines, 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn new_personal_best() {
+        let path = store_path("personal_best");
+        let mut store = ScoreStore::open(&path).unwrap();
+        store.add(record("map", 900_000, 1)).unwrap();
+        store.add(record("map", 850_000, 2)).unwrap();
+        assert_eq!(store.personal_best("map").unwrap().timestamp, 1);
+
+        store.add(record("map", 910_000, 3)).unwrap();
+        assert_eq!(store.personal_best("map").unwrap().timestamp, 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped_and_counted() {
+        let path = store_path("malformed");
+        let mut store = ScoreStore::open(&path).unwrap();
+        store.add(record("map", 900_000, 1)).unwrap();
+
+        // A line cut off by a crash, garbage and a blank line between good records
+        let good = serde_json::to_string(&record("map", 700_000, 2)).unwrap();
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str(&format!(
+            "{}\nnot json\n\n{}\n",
+            &good[..good.len() / 2],
+            good
+        ));
+        std::fs::write(&path, content).unwrap();
+
+        let store = ScoreStore::open(&path).unwrap();
+        assert_eq!(store.skipped_lines, 2);
+        assert_eq!(timestamps(&store.leaderboard("map")), [1, 2]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_empty() {
+        let store = ScoreStore::open(&store_path("missing")).unwrap();
+        assert!(store.records().is_empty());
+        assert_eq!(store.skipped_lines, 0);
+    }
+}
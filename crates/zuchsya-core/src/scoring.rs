@@ -51,6 +51,32 @@ impl HitResult {
         }
     }
 
+    /// Get display string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Perfect => "PERFECT",
+            Self::Great => "GREAT",
+            Self::Good => "GOOD",
+            Self::Ok => "OK",
+            Self::Meh => "MEH",
+            Self::Miss => "MISS",
+        }
+    }
+
+    /// Get health change (fraction of the bar) for a given HP drain rate (0-10)
+    ///
+    /// Based on osu!mania: higher HP drain shrinks gains and grows losses.
+    pub fn health_increase(&self, drain_rate: f64) -> f64 {
+        match self {
+            Self::Perfect => 0.0055 - drain_rate * 0.0005,
+            Self::Great => 0.005 - drain_rate * 0.0005,
+            Self::Good => 0.004 - drain_rate * 0.0004,
+            Self::Ok => 0.0,
+            Self::Meh => -(drain_rate + 1.0) * 0.0016,
+            Self::Miss => -(drain_rate + 1.0) * 0.0075,
+        }
+    }
+
     /// Check if this result breaks combo
     pub fn breaks_combo(&self) -> bool {
         matches!(self, Self::Miss)
@@ -102,6 +128,40 @@ impl ScoreRank {
     }
 }
 
+/// Final result of a play
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreSummary {
+    pub score: i64,
+    pub accuracy: f64,
+    pub max_combo: u32,
+    pub perfect: u32,
+    pub great: u32,
+    pub good: u32,
+    pub ok: u32,
+    pub meh: u32,
+    pub miss: u32,
+}
+
+impl ScoreSummary {
+    /// Get count for a specific result
+    pub fn count_for(&self, result: HitResult) -> u32 {
+        match result {
+            HitResult::Perfect => self.perfect,
+            HitResult::Great => self.great,
+            HitResult::Good => self.good,
+            HitResult::Ok => self.ok,
+            HitResult::Meh => self.meh,
+            HitResult::Miss => self.miss,
+        }
+    }
+
+    /// Calculate rank
+    pub fn rank(&self) -> ScoreRank {
+        let has_imperfect = self.good + self.ok + self.meh + self.miss > 0;
+        ScoreRank::from_accuracy(self.accuracy, has_imperfect)
+    }
+}
+
 /// Difficulty range for hit windows
 /// (max, mid, min) corresponds to (OD 0, OD 5, OD 10)
 #[derive(Debug, Clone, Copy)]
@@ -131,6 +191,9 @@ impl DifficultyRange {
 /// Hit windows based on osu!mania
 pub struct HitWindows {
     pub overall_difficulty: f64,
+    /// Playback rate - windows are in map time, so they grow with the rate to stay
+    /// the same length in real time
+    pub rate: f64,
 }
 
 impl HitWindows {
@@ -145,9 +208,16 @@ impl HitWindows {
     pub fn new(od: f64) -> Self {
         Self {
             overall_difficulty: od,
+            rate: 1.0,
         }
     }
 
+    /// Windows for a map played at `rate`
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
     /// Get window for a specific result
     pub fn window_for(&self, result: HitResult) -> f64 {
         let range = match result {
@@ -158,7 +228,7 @@ impl HitWindows {
             HitResult::Meh => Self::MEH,
             HitResult::Miss => Self::MISS,
         };
-        range.at(self.overall_difficulty).floor() + 0.5
+        (range.at(self.overall_difficulty).floor() + 0.5) * self.rate
     }
 
     /// Determine result from time offset
@@ -30,3 +30,75 @@ impl Default for ScrollVelocity {
         }
     }
 }
+
+/// Precomputed scroll position curve
+///
+/// Integrates scroll velocity over time so the scroll distance between any two
+/// times is an O(log n) lookup. Positions are in milliseconds at 1.0x speed,
+/// so a map without SV changes has `position_at(t) == t`.
+#[derive(Debug, Clone, Default)]
+pub struct ScrollCurve {
+    /// SV points with the integrated position at their start, sorted by time
+    segments: Vec<ScrollSegment>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScrollSegment {
+    time: f64,
+    position: f64,
+    multiplier: f64,
+}
+
+impl ScrollSegment {
+    fn position_at(&self, time: f64) -> f64 {
+        self.position + (time - self.time) * self.multiplier
+    }
+}
+
+impl ScrollCurve {
+    /// Build the curve from scroll velocity points (in any order)
+    ///
+    /// Scrolling runs at 1.0x before the first point.
+    pub fn new(velocities: &[ScrollVelocity]) -> Self {
+        let mut sorted: Vec<&ScrollVelocity> = velocities.iter().collect();
+        sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut segments: Vec<ScrollSegment> = Vec::with_capacity(sorted.len());
+        for sv in sorted {
+            let position = match segments.last() {
+                Some(prev) => prev.position_at(sv.time),
+                None => sv.time,
+            };
+            segments.push(ScrollSegment {
+                time: sv.time,
+                position,
+                multiplier: sv.multiplier,
+            });
+        }
+
+        Self { segments }
+    }
+
+    /// Check if the curve has no SV changes (linear scrolling)
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Get the integrated scroll position at a time
+    pub fn position_at(&self, time: f64) -> f64 {
+        match self.segment_at(time) {
+            Some(segment) => segment.position_at(time),
+            None => time,
+        }
+    }
+
+    /// Get the scroll speed multiplier at a time
+    pub fn multiplier_at(&self, time: f64) -> f64 {
+        self.segment_at(time).map_or(1.0, |s| s.multiplier)
+    }
+
+    fn segment_at(&self, time: f64) -> Option<&ScrollSegment> {
+        let index = self.segments.partition_point(|s| s.time <= time);
+        index.checked_sub(1).map(|i| &self.segments[i])
+    }
+}
@@ -0,0 +1,405 @@
+//! Star rating - strain based difficulty calculation
+//!
+//! Follows osu!mania's strain model: every note adds to the strain of its column
+//! (individual strain) and of the whole chart (overall strain), both decaying over
+//! time. Notes pressed while a hold in another column is still held are harder,
+//! especially when that hold is released shortly before or after them. The
+//! highest strain of each section is weighted into the final rating, hardest
+//! sections first.
+
+use crate::{HitObject, ZuchsyaMap};
+
+/// Length of a strain section in ms (of playback time, after the rate is applied)
+pub const STRAIN_SECTION_LENGTH: f64 = 400.0;
+
+/// Per-second decay of a column's strain
+const INDIVIDUAL_DECAY_BASE: f64 = 0.125;
+
+/// Per-second decay of the overall strain
+const OVERALL_DECAY_BASE: f64 = 0.30;
+
+/// Releases this close (ms) to a note's hold end are the hardest
+const RELEASE_THRESHOLD: f64 = 24.0;
+
+/// Weight falloff of each next-hardest section
+const SECTION_DECAY_WEIGHT: f64 = 0.9;
+
+/// Scales the weighted strain sum to stars
+const STAR_SCALING_FACTOR: f64 = 0.018;
+
+/// Result of a difficulty calculation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DifficultyAttributes {
+    /// Star rating
+    pub star_rating: f64,
+    /// Highest strain of each `STRAIN_SECTION_LENGTH` section, in chart order
+    pub strain_peaks: Vec<f64>,
+    /// Playback rate the rating was calculated for
+    pub rate: f64,
+}
+
+impl ZuchsyaMap {
+    /// Calculate the difficulty of the map played at `rate` (1.0 = normal speed)
+    pub fn difficulty_attributes(&self, rate: f64) -> DifficultyAttributes {
+        calculate_difficulty(&self.hit_objects, self.difficulty.keys, rate)
+    }
+
+    /// Star rating at normal speed
+    pub fn star_rating(&self) -> f64 {
+        self.difficulty_attributes(1.0).star_rating
+    }
+}
+
+/// Calculate the difficulty of hit objects played with `keys` columns at `rate`
+pub fn calculate_difficulty(
+    hit_objects: &[HitObject],
+    keys: u8,
+    rate: f64,
+) -> DifficultyAttributes {
+    let rate = if rate > 0.0 && rate.is_finite() {
+        rate
+    } else {
+        1.0
+    };
+
+    let mut objects: Vec<&HitObject> = hit_objects
+        .iter()
+        .filter(|obj| obj.lane < keys && obj.time.is_finite())
+        .collect();
+    objects.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.lane.cmp(&b.lane)));
+
+    let mut strain = Strain::new(keys);
+    let mut peaks = Vec::new();
+    let mut section_end: Option<f64> = None;
+    let mut section_peak = 0.0;
+
+    // The first object has nothing before it to be difficult against
+    for pair in objects.windows(2) {
+        let (previous, current) = (pair[0], pair[1]);
+        let previous_start = previous.time / rate;
+        let start = current.time / rate;
+        let end = current.end_time() / rate;
+        let delta = start - previous_start;
+
+        let section_end = section_end
+            .get_or_insert_with(|| (start / STRAIN_SECTION_LENGTH).ceil() * STRAIN_SECTION_LENGTH);
+        while start > *section_end {
+            peaks.push(section_peak);
+            // A new section starts at the strain decayed to its beginning
+            section_peak = strain.decayed(*section_end - previous_start);
+            *section_end += STRAIN_SECTION_LENGTH;
+        }
+
+        strain.process(current.lane as usize, start, end, delta);
+        section_peak = f64::max(section_peak, strain.current);
+    }
+    if objects.len() > 1 {
+        peaks.push(section_peak);
+    }
+
+    let mut sorted = peaks.clone();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let mut weight = 1.0;
+    let mut difficulty = 0.0;
+    for peak in sorted.into_iter().filter(|peak| *peak > 0.0) {
+        difficulty += peak * weight;
+        weight *= SECTION_DECAY_WEIGHT;
+    }
+
+    DifficultyAttributes {
+        star_rating: difficulty * STAR_SCALING_FACTOR,
+        strain_peaks: peaks,
+        rate,
+    }
+}
+
+/// Running strain state
+struct Strain {
+    /// Strain of the chart so far
+    current: f64,
+    /// Start time of the last note in each column
+    start_times: Vec<f64>,
+    /// End time of the last note in each column
+    end_times: Vec<f64>,
+    /// Strain of each column
+    individual_strains: Vec<f64>,
+    /// Column strain of the current chord
+    individual_strain: f64,
+    overall_strain: f64,
+}
+
+impl Strain {
+    fn new(keys: u8) -> Self {
+        Self {
+            current: 0.0,
+            start_times: vec![0.0; keys as usize],
+            end_times: vec![0.0; keys as usize],
+            individual_strains: vec![0.0; keys as usize],
+            individual_strain: 0.0,
+            overall_strain: 1.0,
+        }
+    }
+
+    /// Strain `delta` ms after the last note, with nothing pressed since
+    fn decayed(&self, delta: f64) -> f64 {
+        apply_decay(self.individual_strain, delta, INDIVIDUAL_DECAY_BASE)
+            + apply_decay(self.overall_strain, delta, OVERALL_DECAY_BASE)
+    }
+
+    /// Add a note starting at `start` and ending at `end`, `delta` ms after the previous one
+    fn process(&mut self, column: usize, start: f64, end: f64, delta: f64) {
+        let mut hold_factor = 1.0;
+        let mut hold_addition = 0.0;
+        let mut is_overlapping = false;
+        // Lowest value we can assume with the current information
+        let mut closest_end_time = (end - start).abs();
+
+        for &hold_end in &self.end_times {
+            // The note is pressed during a hold in another column and outlasts it
+            is_overlapping |= hold_end > start + 1.0 && end > hold_end + 1.0;
+            // The note is inside a hold in another column
+            if hold_end > end + 1.0 {
+                hold_factor = 1.25;
+            }
+            closest_end_time = closest_end_time.min((end - hold_end).abs());
+        }
+
+        // Releasing a hold close to another note's release is hard
+        if is_overlapping {
+            hold_addition = 1.0 / (1.0 + (0.5 * (RELEASE_THRESHOLD - closest_end_time)).exp());
+        }
+
+        self.individual_strains[column] = apply_decay(
+            self.individual_strains[column],
+            start - self.start_times[column],
+            INDIVIDUAL_DECAY_BASE,
+        );
+        self.individual_strains[column] += 2.0 * hold_factor;
+
+        // Chords count as hard as their hardest column
+        self.individual_strain = if delta <= 1.0 {
+            self.individual_strain.max(self.individual_strains[column])
+        } else {
+            self.individual_strains[column]
+        };
+
+        self.overall_strain = apply_decay(self.overall_strain, delta, OVERALL_DECAY_BASE);
+        self.overall_strain += (1.0 + hold_addition) * hold_factor;
+
+        self.start_times[column] = start;
+        self.end_times[column] = end;
+
+        self.current = self.individual_strain + self.overall_strain;
+    }
+}
+
+/// Decay `value` over `delta` ms, with `base` left after one second
+fn apply_decay(value: f64, delta: f64, base: f64) -> f64 {
+    value * base.powf(delta / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Single column jack, 8 notes at 150ms
+    fn jack() -> Vec<HitObject> {
+        (0..8)
+            .map(|i| HitObject::note(0, i as f64 * 150.0))
+            .collect()
+    }
+
+    /// 1234 stream, 16 notes at 100ms
+    fn stream() -> Vec<HitObject> {
+        (0..16)
+            .map(|i| HitObject::note(i % 4, i as f64 * 100.0))
+            .collect()
+    }
+
+    /// [12][34] jumps, 8 chords at 200ms
+    fn chords() -> Vec<HitObject> {
+        (0..8)
+            .flat_map(|i| {
+                let time = i as f64 * 200.0;
+                let lanes = if i % 2 == 0 { [0, 1] } else { [2, 3] };
+                lanes.map(|lane| HitObject::note(lane, time))
+            })
+            .collect()
+    }
+
+    /// Holds pressed during a hold in the next column, released shortly after it
+    fn hold_overlap() -> Vec<HitObject> {
+        (0..6)
+            .flat_map(|i| {
+                let time = i as f64 * 300.0;
+                [
+                    HitObject::hold(0, time, 250.0),
+                    HitObject::hold(1, time + 100.0, 170.0),
+                ]
+            })
+            .collect()
+    }
+
+    /// A chart repeated every `period` ms for a minute
+    fn repeated(chart: Vec<HitObject>, period: f64) -> Vec<HitObject> {
+        (0..(60000.0 / period) as usize)
+            .flat_map(|i| {
+                chart.iter().map(move |obj| {
+                    let mut obj = obj.clone();
+                    obj.time += i as f64 * period;
+                    obj
+                })
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn assert_peaks(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert_close(*actual, *expected);
+        }
+    }
+
+    // The two reference charts below are small enough to work out by hand. Their
+    // expected values follow the strain formulas of osu!lazer's osu!mania
+    // difficulty calculator (`ManiaDifficultyCalculator` and its `Strain` skill)
+    // step by step, independently of this implementation. The pinned values of the
+    // larger charts after them only guard against regressions.
+
+    #[test]
+    fn reference_two_notes() {
+        // The first note is skipped. The second, 500ms later in the same column:
+        //   individual = 2
+        //   overall    = 1 * 0.3^0.5 + 1                        = 1.5477225575051661
+        //   strain     = 3.5477225575051661, the only section peak (0-800ms)
+        //   stars      = 3.5477225575051661 * 0.018             = 0.0638590060350930
+        let chart = [HitObject::note(0, 0.0), HitObject::note(0, 500.0)];
+        let attributes = calculate_difficulty(&chart, 4, 1.0);
+        assert_peaks(&attributes.strain_peaks, &[3.547722557505166]);
+        assert_close(attributes.star_rating, 0.0638590060350930);
+    }
+
+    #[test]
+    fn reference_holds() {
+        // 100ms hold(0, 100-1000): individual 2, overall 1 * 0.3^0.1 + 1 = 1.8865681505652
+        //   strain 3.8865681505652, peak of section 0-400ms
+        // Section 400-800ms starts at 2 * 0.125^0.3 + 1.8865681505652 * 0.3^0.3
+        //   = 2.3864196150397
+        // 500ms note(1) inside the hold, hold factor 1.25:
+        //   individual 2 * 1.25 = 2.5
+        //   overall 1.8865681505652 * 0.3^0.4 + 1.25 = 2.4155234080726
+        //   strain 4.9155234080726, peak of section 400-800ms
+        // 700ms hold(2, 700-1010) outlasting hold(0) by 10ms:
+        //   hold addition 1 / (1 + e^(0.5 * (24 - 10))) = 0.0009110511944
+        //   overall 2.4155234080726 * 0.3^0.2 + 1.0009110511944 = 2.8995199032703
+        //   strain 2 + 2.8995199032703 = 4.8995199032703
+        // stars = (4.9155234080726 + 3.8865681505652 * 0.9) * 0.018 = 0.1514418253845
+        let chart = [
+            HitObject::note(1, 0.0),
+            HitObject::hold(0, 100.0, 900.0),
+            HitObject::note(1, 500.0),
+            HitObject::hold(2, 700.0, 310.0),
+        ];
+        let attributes = calculate_difficulty(&chart, 4, 1.0);
+        assert_peaks(
+            &attributes.strain_peaks,
+            &[3.886568150565213, 4.915523408072578],
+        );
+        assert_close(attributes.star_rating, 0.15144182538446285);
+    }
+
+    #[test]
+    fn jack_rating() {
+        let attributes = calculate_difficulty(&jack(), 4, 1.0);
+        assert_close(attributes.star_rating, 0.4502492294756593);
+        assert_peaks(
+            &attributes.strain_peaks,
+            &[5.995703602808179, 9.899071129939994, 11.248162146760453],
+        );
+    }
+
+    #[test]
+    fn stream_rating() {
+        let attributes = calculate_difficulty(&stream(), 4, 1.0);
+        assert_close(attributes.star_rating, 0.5680882985639724);
+        assert_peaks(
+            &attributes.strain_peaks,
+            &[
+                5.987217388665197,
+                8.703273395508443,
+                10.222357369886243,
+                10.946006471735412,
+            ],
+        );
+    }
+
+    #[test]
+    fn chord_rating() {
+        let attributes = calculate_difficulty(&chords(), 4, 1.0);
+        assert_close(attributes.star_rating, 0.6745896405223976);
+        assert_peaks(
+            &attributes.strain_peaks,
+            &[
+                4.0,
+                7.678158435624193,
+                9.791630108835836,
+                11.028170449040422,
+                11.39885095938087,
+            ],
+        );
+    }
+
+    #[test]
+    fn hold_overlap_rating() {
+        let attributes = calculate_difficulty(&hold_overlap(), 4, 1.0);
+        assert_close(attributes.star_rating, 0.5261635094177316);
+        assert_peaks(
+            &attributes.strain_peaks,
+            &[
+                6.392190687626985,
+                7.965710856393791,
+                8.969764570826012,
+                10.046385160060415,
+            ],
+        );
+    }
+
+    #[test]
+    fn rating_increases_with_rate() {
+        // Long enough that the number of sections doesn't change the rating
+        let charts = [
+            repeated(jack(), 1200.0),
+            repeated(stream(), 1600.0),
+            repeated(chords(), 1600.0),
+            repeated(hold_overlap(), 1800.0),
+        ];
+        for chart in charts {
+            let ratings: Vec<f64> = [0.5, 0.75, 1.0, 1.5, 2.0]
+                .into_iter()
+                .map(|rate| calculate_difficulty(&chart, 4, rate).star_rating)
+                .collect();
+            assert!(ratings.is_sorted_by(|a, b| a < b), "{ratings:?}");
+        }
+    }
+
+    #[test]
+    fn empty_sections_decay() {
+        let chart = [
+            HitObject::note(0, 0.0),
+            HitObject::note(1, 100.0),
+            HitObject::note(2, 2000.0),
+        ];
+        let peaks = calculate_difficulty(&chart, 4, 1.0).strain_peaks;
+        // Sections between the second and third notes only hold decayed strain
+        let empty = &peaks[1..peaks.len() - 1];
+        assert!(!empty.is_empty());
+        assert!(empty.is_sorted_by(|a, b| a > b), "{peaks:?}");
+    }
+}
@@ -16,6 +16,8 @@ pub enum GameState {
     SongSelect,
     /// Gameplay
     Playing,
+    /// Leaving gameplay to immediately start it again (retry)
+    Restarting,
     /// Results screen
     Results,
     /// Beatmap editor
@@ -23,3 +25,18 @@ pub enum GameState {
     /// Settings screen
     Settings,
 }
+
+/// Gameplay sub-state (only exists while in GameState::Playing)
+#[derive(SubStates, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum PlayState {
+    /// Notes scrolling and being judged
+    #[default]
+    Running,
+    /// Paused by the player - time, audio and judgement are frozen
+    Paused,
+    /// Counting down before resuming from pause
+    Resuming,
+    /// Health ran out - play is stopped
+    Failed,
+}
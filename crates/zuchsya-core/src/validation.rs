@@ -0,0 +1,553 @@
+//! Beatmap validation
+//!
+//! Checks a beatmap for every problem at once instead of stopping at the first.
+//! Errors make a map unplayable and are rejected when loading; warnings are
+//! suspicious data the game can still cope with.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::{HitObject, ZuchsyaMap};
+
+/// How bad a validation problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Suspicious, but the map can still be played
+    Warning,
+    /// The map can't be played correctly
+    Error,
+}
+
+/// Part of the beatmap a diagnostic refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// The beatmap as a whole
+    Map,
+    /// Key count
+    Keys,
+    /// Overall difficulty
+    Od,
+    /// HP drain
+    Hp,
+    /// Audio file
+    Audio,
+    /// Background image
+    Background,
+    /// Index into `timing`
+    TimingPoint(usize),
+    /// Index into `scroll_velocities`
+    ScrollVelocity(usize),
+    /// Index into `hit_objects`
+    HitObject(usize),
+    /// Index into `editor.breaks`
+    Break(usize),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Map => write!(f, "beatmap"),
+            Location::Keys => write!(f, "difficulty.keys"),
+            Location::Od => write!(f, "difficulty.od"),
+            Location::Hp => write!(f, "difficulty.hp"),
+            Location::Audio => write!(f, "audio.file"),
+            Location::Background => write!(f, "background"),
+            Location::TimingPoint(i) => write!(f, "timing[{}]", i),
+            Location::ScrollVelocity(i) => write!(f, "scroll_velocities[{}]", i),
+            Location::HitObject(i) => write!(f, "hit_objects[{}]", i),
+            Location::Break(i) => write!(f, "editor.breaks[{}]", i),
+        }
+    }
+}
+
+/// A problem found in a beatmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub location: Location,
+    /// Time in ms the problem is at, if it has one
+    pub time: Option<f64>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(location: Location, time: Option<f64>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            location,
+            time,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(location: Location, time: Option<f64>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            location,
+            time,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", severity, self.location)?;
+        if let Some(time) = self.time.filter(|t| t.is_finite()) {
+            write!(f, " ({}ms)", time)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl ZuchsyaMap {
+    /// Check the beatmap data for problems
+    ///
+    /// Doesn't look at files, see `asset_diagnostics` for that.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.check_difficulty(&mut diagnostics);
+        self.check_timing(&mut diagnostics);
+        self.check_hit_objects(&mut diagnostics);
+        self.check_breaks(&mut diagnostics);
+        diagnostics
+    }
+
+    /// Check that the files the beatmap references exist in `folder`
+    pub fn asset_diagnostics(&self, folder: &Path) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        if self.audio.file.is_empty() {
+            diagnostics.push(Diagnostic::warning(
+                Location::Audio,
+                None,
+                "No audio file set",
+            ));
+        } else if !folder.join(&self.audio.file).is_file() {
+            // Playable without music, so only a warning
+            diagnostics.push(Diagnostic::warning(
+                Location::Audio,
+                None,
+                format!("Audio file {} is missing", self.audio.file),
+            ));
+        }
+
+        if let Some(background) = &self.background
+            && !folder.join(background).is_file()
+        {
+            diagnostics.push(Diagnostic::warning(
+                Location::Background,
+                None,
+                format!("Background {} is missing", background),
+            ));
+        }
+
+        diagnostics
+    }
+
+    fn check_difficulty(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let difficulty = &self.difficulty;
+        if !(1..=10).contains(&difficulty.keys) {
+            diagnostics.push(Diagnostic::error(
+                Location::Keys,
+                None,
+                format!("Key count {} is outside 1-10", difficulty.keys),
+            ));
+        }
+        if !(0.0..=10.0).contains(&difficulty.od) {
+            diagnostics.push(Diagnostic::error(
+                Location::Od,
+                None,
+                format!("OD {} is outside 0-10", difficulty.od),
+            ));
+        }
+        if !(0.0..=10.0).contains(&difficulty.hp) {
+            diagnostics.push(Diagnostic::error(
+                Location::Hp,
+                None,
+                format!("HP {} is outside 0-10", difficulty.hp),
+            ));
+        }
+    }
+
+    fn check_timing(&self, diagnostics: &mut Vec<Diagnostic>) {
+        if self.timing.is_empty() {
+            diagnostics.push(Diagnostic::error(
+                Location::Map,
+                None,
+                "At least one timing point is required",
+            ));
+        }
+
+        // Timing may start before 0 (audio offset), so negative times are fine here
+        for (i, point) in self.timing.iter().enumerate() {
+            let location = Location::TimingPoint(i);
+            if !point.time.is_finite() {
+                diagnostics.push(Diagnostic::error(location, None, "Time is not a number"));
+                continue;
+            }
+            if !(point.bpm > 0.0 && point.bpm.is_finite()) {
+                diagnostics.push(Diagnostic::error(
+                    location,
+                    Some(point.time),
+                    format!("BPM {} must be positive", point.bpm),
+                ));
+            }
+            if i > 0 && point.time < self.timing[i - 1].time {
+                diagnostics.push(Diagnostic::warning(
+                    location,
+                    Some(point.time),
+                    "Timing point comes before the previous one",
+                ));
+            }
+        }
+
+        for (i, sv) in self.scroll_velocities.iter().enumerate() {
+            let location = Location::ScrollVelocity(i);
+            if !sv.time.is_finite() {
+                diagnostics.push(Diagnostic::error(location, None, "Time is not a number"));
+                continue;
+            }
+            if !sv.multiplier.is_finite() {
+                diagnostics.push(Diagnostic::error(
+                    location,
+                    Some(sv.time),
+                    "Multiplier is not a number",
+                ));
+            }
+            if i > 0 && sv.time < self.scroll_velocities[i - 1].time {
+                diagnostics.push(Diagnostic::warning(
+                    location,
+                    Some(sv.time),
+                    "Scroll velocity comes before the previous one",
+                ));
+            }
+        }
+    }
+
+    fn check_hit_objects(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let keys = self.difficulty.keys;
+        let mut lanes: Vec<Vec<usize>> = vec![Vec::new(); keys as usize];
+
+        for (i, obj) in self.hit_objects.iter().enumerate() {
+            let location = Location::HitObject(i);
+            if !obj.time.is_finite() {
+                diagnostics.push(Diagnostic::error(location, None, "Time is not a number"));
+                continue;
+            }
+            let time = Some(obj.time);
+
+            if obj.time < 0.0 {
+                diagnostics.push(Diagnostic::warning(location, time, "Time is negative"));
+            }
+            if i > 0 && obj.time < self.hit_objects[i - 1].time {
+                diagnostics.push(Diagnostic::warning(
+                    location,
+                    time,
+                    "Hit object comes before the previous one",
+                ));
+            }
+            if let Some(duration) = obj.duration
+                && !(duration > 0.0 && duration.is_finite())
+            {
+                diagnostics.push(Diagnostic::error(
+                    location,
+                    time,
+                    format!("Hold duration {} must be positive", duration),
+                ));
+                continue;
+            }
+
+            match lanes.get_mut(obj.lane as usize) {
+                Some(lane) => lane.push(i),
+                None => diagnostics.push(Diagnostic::error(
+                    location,
+                    time,
+                    format!("Lane {} is outside 0-{}", obj.lane, keys.saturating_sub(1)),
+                )),
+            }
+        }
+
+        for mut lane in lanes {
+            lane.sort_by(|&a, &b| {
+                self.hit_objects[a]
+                    .time
+                    .total_cmp(&self.hit_objects[b].time)
+            });
+            check_lane_overlaps(&self.hit_objects, &lane, diagnostics);
+        }
+    }
+
+    fn check_breaks(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(editor) = &self.editor else {
+            return;
+        };
+
+        for (i, brk) in editor.breaks.iter().enumerate() {
+            let location = Location::Break(i);
+            if !(brk.start.is_finite() && brk.end.is_finite()) {
+                diagnostics.push(Diagnostic::error(location, None, "Time is not a number"));
+                continue;
+            }
+            let time = Some(brk.start);
+
+            if brk.end <= brk.start {
+                diagnostics.push(Diagnostic::warning(
+                    location,
+                    time,
+                    "Break ends before it starts",
+                ));
+                continue;
+            }
+            if brk.start < 0.0 {
+                diagnostics.push(Diagnostic::warning(location, time, "Time is negative"));
+            }
+
+            let overlapping = self
+                .hit_objects
+                .iter()
+                .position(|obj| obj.time < brk.end && obj.end_time() > brk.start);
+            if let Some(index) = overlapping {
+                diagnostics.push(Diagnostic::warning(
+                    location,
+                    time,
+                    format!("Break overlaps hit_objects[{}]", index),
+                ));
+            }
+        }
+    }
+}
+
+/// Report notes stacked on each other or starting inside a hold
+///
+/// `lane` holds the indices of one lane's objects, sorted by time.
+fn check_lane_overlaps(objects: &[HitObject], lane: &[usize], diagnostics: &mut Vec<Diagnostic>) {
+    let mut previous: Option<usize> = None;
+    // Hold reaching furthest so far
+    let mut hold: Option<usize> = None;
+
+    for &i in lane {
+        let obj = &objects[i];
+        let location = Location::HitObject(i);
+
+        if let Some(prev) = previous
+            && objects[prev].time == obj.time
+        {
+            diagnostics.push(Diagnostic::warning(
+                location,
+                Some(obj.time),
+                format!("Overlaps hit_objects[{}] in lane {}", prev, obj.lane),
+            ));
+        } else if let Some(h) = hold
+            && obj.time <= objects[h].end_time()
+        {
+            diagnostics.push(Diagnostic::warning(
+                location,
+                Some(obj.time),
+                format!("Starts inside the hold hit_objects[{}]", h),
+            ));
+        }
+
+        if obj.is_hold() && hold.is_none_or(|h| obj.end_time() > objects[h].end_time()) {
+            hold = Some(i);
+        }
+        previous = Some(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BreakPeriod, EditorInfo, ScrollVelocity, TimingPoint};
+
+    fn map(hit_objects: Vec<HitObject>) -> ZuchsyaMap {
+        ZuchsyaMap {
+            hit_objects,
+            ..ZuchsyaMap::default()
+        }
+    }
+
+    /// Assert the map has exactly one problem, of the given kind
+    fn assert_single(map: &ZuchsyaMap, severity: Severity, location: Location) {
+        let diagnostics = map.diagnostics();
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].severity, severity, "{}", diagnostics[0]);
+        assert_eq!(diagnostics[0].location, location, "{}", diagnostics[0]);
+    }
+
+    #[test]
+    fn valid_map_has_no_diagnostics() {
+        let map = map(vec![
+            HitObject::note(0, 0.0),
+            HitObject::hold(1, 0.0, 500.0),
+            HitObject::note(0, 250.0),
+            HitObject::note(1, 750.0),
+        ]);
+        assert_eq!(map.diagnostics(), Vec::new());
+    }
+
+    #[test]
+    fn lane_outside_keys() {
+        let map = map(vec![HitObject::note(0, 0.0), HitObject::note(4, 100.0)]);
+        assert_single(&map, Severity::Error, Location::HitObject(1));
+    }
+
+    #[test]
+    fn nan_times() {
+        let mut nan_object = map(vec![HitObject::note(0, f64::NAN)]);
+        assert_single(&nan_object, Severity::Error, Location::HitObject(0));
+
+        nan_object.hit_objects.clear();
+        nan_object.timing.push(TimingPoint::new(f64::NAN, 120.0));
+        assert_single(&nan_object, Severity::Error, Location::TimingPoint(1));
+
+        nan_object.timing.pop();
+        nan_object
+            .scroll_velocities
+            .push(ScrollVelocity::new(f64::NAN, 1.0));
+        assert_single(&nan_object, Severity::Error, Location::ScrollVelocity(0));
+    }
+
+    #[test]
+    fn negative_time() {
+        let map = map(vec![HitObject::note(0, -100.0), HitObject::note(1, 0.0)]);
+        assert_single(&map, Severity::Warning, Location::HitObject(0));
+    }
+
+    #[test]
+    fn non_positive_bpm() {
+        for bpm in [0.0, -120.0, f64::INFINITY] {
+            let mut map = map(Vec::new());
+            map.timing = vec![TimingPoint::new(0.0, bpm)];
+            assert_single(&map, Severity::Error, Location::TimingPoint(0));
+        }
+    }
+
+    #[test]
+    fn missing_timing() {
+        let mut map = map(Vec::new());
+        map.timing.clear();
+        assert_single(&map, Severity::Error, Location::Map);
+    }
+
+    #[test]
+    fn unsorted_timing_points() {
+        let mut map = map(Vec::new());
+        map.timing = vec![
+            TimingPoint::new(1000.0, 120.0),
+            TimingPoint::new(0.0, 180.0),
+        ];
+        assert_single(&map, Severity::Warning, Location::TimingPoint(1));
+    }
+
+    #[test]
+    fn unsorted_scroll_velocities() {
+        let mut map = map(Vec::new());
+        map.scroll_velocities = vec![
+            ScrollVelocity::new(0.0, 1.0),
+            ScrollVelocity::new(2000.0, 0.5),
+            ScrollVelocity::new(1000.0, 2.0),
+        ];
+        assert_single(&map, Severity::Warning, Location::ScrollVelocity(2));
+    }
+
+    #[test]
+    fn unsorted_hit_objects() {
+        let map = map(vec![HitObject::note(0, 500.0), HitObject::note(1, 200.0)]);
+        assert_single(&map, Severity::Warning, Location::HitObject(1));
+    }
+
+    #[test]
+    fn same_lane_overlap() {
+        let map = map(vec![
+            HitObject::note(2, 100.0),
+            HitObject::note(3, 100.0),
+            HitObject::note(2, 100.0),
+        ]);
+        assert_single(&map, Severity::Warning, Location::HitObject(2));
+    }
+
+    #[test]
+    fn note_inside_hold() {
+        let map = map(vec![
+            HitObject::hold(0, 0.0, 1000.0),
+            HitObject::note(1, 200.0),
+            HitObject::note(0, 500.0),
+            // Another lane's hold doesn't matter
+            HitObject::note(1, 600.0),
+        ]);
+        assert_single(&map, Severity::Warning, Location::HitObject(2));
+    }
+
+    #[test]
+    fn non_positive_hold_duration() {
+        for duration in [0.0, -100.0, f64::NAN] {
+            let map = map(vec![HitObject::hold(0, 0.0, duration)]);
+            assert_single(&map, Severity::Error, Location::HitObject(0));
+        }
+    }
+
+    #[test]
+    fn difficulty_out_of_range() {
+        let mut map = map(Vec::new());
+        map.difficulty.od = 10.5;
+        assert_single(&map, Severity::Error, Location::Od);
+
+        map.difficulty.od = 5.0;
+        map.difficulty.hp = -1.0;
+        assert_single(&map, Severity::Error, Location::Hp);
+
+        map.difficulty.hp = 5.0;
+        map.difficulty.keys = 0;
+        assert_single(&map, Severity::Error, Location::Keys);
+    }
+
+    #[test]
+    fn break_overlapping_notes() {
+        let mut map = map(vec![
+            HitObject::note(0, 0.0),
+            HitObject::hold(1, 500.0, 1000.0),
+        ]);
+        map.editor = Some(EditorInfo {
+            breaks: vec![
+                BreakPeriod {
+                    start: 100.0,
+                    end: 400.0,
+                },
+                BreakPeriod {
+                    start: 1200.0,
+                    end: 3000.0,
+                },
+            ],
+            ..EditorInfo::default()
+        });
+        assert_single(&map, Severity::Warning, Location::Break(1));
+    }
+
+    #[test]
+    fn missing_audio() {
+        let folder =
+            std::env::temp_dir().join(format!("zuchsya-validation-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let mut map = map(Vec::new());
+        map.audio.file = "audio.mp3".into();
+        let diagnostics = map.asset_diagnostics(&folder);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].location, Location::Audio);
+
+        std::fs::write(folder.join("audio.mp3"), b"").unwrap();
+        assert_eq!(map.asset_diagnostics(&folder), Vec::new());
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+//! Every fixture in `fixtures/format/` must keep loading after format changes
+
+use std::path::{Path, PathBuf};
+
+use zuchsya_core::{BeatmapError, FORMAT_VERSION, ZuchsyaMap};
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/format");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zuchsya"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no fixtures in {}", dir.display());
+    paths
+}
+
+#[test]
+fn fixtures_load() {
+    for path in fixtures() {
+        let content = std::fs::read_to_string(&path).unwrap();
+        if let Err(err) = ZuchsyaMap::from_yaml(&content) {
+            panic!("{}: {}", path.display(), err);
+        }
+    }
+}
+
+#[test]
+fn fixtures_resave_to_current_version() {
+    let dir = std::env::temp_dir().join(format!("zuchsya-fixtures-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    for path in fixtures() {
+        // Resaving rewrites the file, so work on a copy
+        let copy = dir.join(path.file_name().unwrap());
+        std::fs::copy(&path, &copy).unwrap();
+
+        let original = ZuchsyaMap::from_yaml_migrated(&std::fs::read_to_string(&copy).unwrap())
+            .unwrap()
+            .1;
+        let resaved = ZuchsyaMap::resave(&copy).unwrap();
+        assert_eq!(resaved, original != FORMAT_VERSION, "{}", path.display());
+
+        let (map, version) =
+            ZuchsyaMap::from_yaml_migrated(&std::fs::read_to_string(&copy).unwrap()).unwrap();
+        assert_eq!(version, FORMAT_VERSION, "{}", path.display());
+        assert_eq!(
+            map.hit_objects.len(),
+            ZuchsyaMap::load(&path).unwrap().hit_objects.len()
+        );
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn newer_version_is_unsupported() {
+    let newer = FORMAT_VERSION + 1;
+    let yaml = format!("version: {}\nmetadata:\n  title: Future\n", newer);
+    assert!(matches!(
+        ZuchsyaMap::from_yaml(&yaml),
+        Err(BeatmapError::UnsupportedVersion { found, supported })
+            if found == newer && supported == FORMAT_VERSION
+    ));
+}
+
+#[test]
+fn missing_or_invalid_version() {
+    assert!(matches!(
+        ZuchsyaMap::from_yaml("metadata:\n  title: No version\n"),
+        Err(BeatmapError::MissingVersion)
+    ));
+    assert!(matches!(
+        ZuchsyaMap::from_yaml("version: zero\n"),
+        Err(BeatmapError::InvalidVersion(_))
+    ));
+    assert!(matches!(
+        ZuchsyaMap::from_yaml("version: 0\n"),
+        Err(BeatmapError::InvalidVersion(_))
+    ));
+}
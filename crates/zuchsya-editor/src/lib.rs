@@ -1,7 +1,9 @@
 //! Zuchsya Editor - Beatmap editor systems
 
+use std::path::{Path, PathBuf};
+
 use bevy::prelude::*;
-use zuchsya_core::GameState;
+use zuchsya_core::{BeatmapError, Diagnostic, GameState, Severity, ZuchsyaMap};
 
 /// Editor plugin - adds all editor systems
 pub struct EditorPlugin;
@@ -9,14 +11,54 @@ pub struct EditorPlugin;
 impl Plugin for EditorPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Editor), setup_editor)
+            .add_systems(Update, handle_input.run_if(in_state(GameState::Editor)))
             .add_systems(OnExit(GameState::Editor), cleanup_editor);
     }
 }
 
+/// Beatmap opened in the editor
+#[derive(Resource)]
+pub struct EditorBeatmap {
+    pub map: ZuchsyaMap,
+    /// Path of the .zuchsya file (assets are resolved relative to it)
+    pub path: PathBuf,
+}
+
+impl EditorBeatmap {
+    /// Open a beatmap file, even if it has validation errors
+    pub fn load(path: &Path) -> Result<Self, BeatmapError> {
+        let map = ZuchsyaMap::load_unvalidated(path)?;
+        Ok(Self {
+            map,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Folder containing the beatmap file
+    pub fn folder(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Problems in the beatmap data and its referenced files
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.map.diagnostics();
+        diagnostics.extend(self.map.asset_diagnostics(self.folder()));
+        diagnostics
+    }
+}
+
+/// Text color for a diagnostic of the given severity
+pub fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Warning => Color::srgb(0.9, 0.75, 0.3),
+        Severity::Error => Color::srgb(0.9, 0.35, 0.35),
+    }
+}
+
 #[derive(Component)]
 struct EditorRoot;
 
-fn setup_editor(mut commands: Commands) {
+fn setup_editor(mut commands: Commands, beatmap: Option<Res<EditorBeatmap>>) {
     commands
         .spawn((
             EditorRoot,
@@ -25,6 +67,7 @@ fn setup_editor(mut commands: Commands) {
                 height: Val::Percent(100.0),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
                 ..default()
             },
             BackgroundColor(Color::srgb(0.1, 0.1, 0.12)),
@@ -38,9 +81,98 @@ fn setup_editor(mut commands: Commands) {
                 },
                 TextColor(Color::srgb(0.5, 0.5, 0.5)),
             ));
+
+            let Some(beatmap) = beatmap else {
+                parent.spawn((
+                    Text::new("No beatmap open - press E in song select to edit one"),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                    Node {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                ));
+                return;
+            };
+
+            let metadata = &beatmap.map.metadata;
+            parent.spawn((
+                Text::new(format!(
+                    "{} - {} [{}]",
+                    metadata.artist, metadata.title, metadata.difficulty_name
+                )),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Text::new(beatmap.map.analyze_patterns().summary()),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.8)),
+                Node {
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
+
+            // Validation panel
+            let diagnostics = beatmap.diagnostics();
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::top(Val::Px(20.0)),
+                        padding: UiRect::all(Val::Px(15.0)),
+                        min_width: Val::Px(500.0),
+                        max_height: Val::Px(400.0),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.3)),
+                ))
+                .with_children(|panel| {
+                    if diagnostics.is_empty() {
+                        panel.spawn((
+                            Text::new("No problems found"),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.4, 0.8, 0.4)),
+                        ));
+                    }
+                    for diagnostic in &diagnostics {
+                        panel.spawn((
+                            Text::new(diagnostic.to_string()),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(severity_color(diagnostic.severity)),
+                        ));
+                    }
+                });
         });
 }
 
+fn handle_input(keyboard: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
 fn cleanup_editor(mut commands: Commands, query: Query<Entity, With<EditorRoot>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
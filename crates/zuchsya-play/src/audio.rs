@@ -0,0 +1,337 @@
+//! Music playback - plays the beatmap audio and exposes its clock
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::tasks::futures::check_ready;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy_kira_audio::prelude::{
+    Audio, AudioControl, AudioInstance, AudioSource, AudioTween, PlaybackState, StaticSoundData,
+};
+use zuchsya_core::{GameState, PlayState};
+
+use crate::GameplaySet;
+use crate::beatmap::BeatmapLoadSet;
+use crate::time_stretch::time_stretch;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicConfig>()
+            .init_resource::<MusicTrack>()
+            .init_resource::<MusicPlayback>()
+            .init_resource::<MusicCache>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                start_music.after(BeatmapLoadSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    finish_loading_music.run_if(|cache: Res<MusicCache>| cache.is_loading()),
+                    start_loaded_music
+                        .before(GameplaySet::Clock)
+                        .run_if(in_state(PlayState::Running))
+                        .run_if(|playback: Res<MusicPlayback>| playback.loading),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(PlayState::Failed), pause_music)
+            .add_systems(OnEnter(PlayState::Paused), pause_music)
+            .add_systems(OnEnter(PlayState::Running), resume_music)
+            .add_systems(OnExit(GameState::Playing), stop_music);
+    }
+}
+
+/// Music preferences
+#[derive(Resource)]
+pub struct MusicConfig {
+    /// Keep the pitch of the music at rates other than 1.0x (otherwise it plays
+    /// faster/slower like a record)
+    pub preserve_pitch: bool,
+}
+
+impl Default for MusicConfig {
+    fn default() -> Self {
+        Self {
+            preserve_pitch: true,
+        }
+    }
+}
+
+/// Audio file for the current beatmap
+#[derive(Resource)]
+pub struct MusicTrack {
+    /// Resolved path to the audio file (None = play without music)
+    pub path: Option<PathBuf>,
+    /// Playback rate (rate mods)
+    pub rate: f64,
+    /// Track position to start playing from in ms (practice)
+    pub start_ms: f64,
+}
+
+impl Default for MusicTrack {
+    fn default() -> Self {
+        Self {
+            path: None,
+            rate: 1.0,
+            start_ms: 0.0,
+        }
+    }
+}
+
+/// Playing music instance for the current play
+#[derive(Resource)]
+pub struct MusicPlayback {
+    instance: Option<Handle<AudioInstance>>,
+    /// Track time per second of the played sound - the rate if the track was time
+    /// stretched, 1.0 otherwise
+    position_scale: f64,
+    /// Waiting for the track to be decoded (and time stretched) before starting it
+    loading: bool,
+}
+
+impl Default for MusicPlayback {
+    fn default() -> Self {
+        Self {
+            instance: None,
+            position_scale: 1.0,
+            loading: false,
+        }
+    }
+}
+
+/// Decoded music, kept for retries and practice loops of the same track
+///
+/// Only the last track is kept per kind, a decoded song takes tens of MB.
+#[derive(Resource, Default)]
+struct MusicCache {
+    /// Track as decoded, played resampled at the rate
+    decoded: LoadedMusic<PathBuf>,
+    /// Track time stretched to a rate
+    stretched: LoadedMusic<(PathBuf, f64)>,
+}
+
+impl MusicCache {
+    fn is_loading(&self) -> bool {
+        self.decoded.task.is_some() || self.stretched.task.is_some()
+    }
+}
+
+/// A track loaded in the background
+#[derive(Default)]
+struct LoadedMusic<K> {
+    /// What is being loaded or was loaded
+    key: Option<K>,
+    /// Loading in the background
+    task: Option<Task<Option<StaticSoundData>>>,
+    /// Loaded track
+    source: Option<Handle<AudioSource>>,
+}
+
+impl<K: PartialEq> LoadedMusic<K> {
+    /// Start loading `key` unless it is already loaded or loading
+    fn load(
+        &mut self,
+        key: K,
+        load: impl Future<Output = Option<StaticSoundData>> + Send + 'static,
+    ) {
+        if self.key.as_ref() != Some(&key) {
+            self.task = Some(AsyncComputeTaskPool::get().spawn(load));
+            self.source = None;
+            self.key = Some(key);
+        }
+    }
+
+    fn poll(&mut self, sources: &mut Assets<AudioSource>) {
+        let Some(task) = &mut self.task else {
+            return;
+        };
+        let Some(sound) = check_ready(task) else {
+            return;
+        };
+
+        self.task = None;
+        self.source = sound.map(|sound| sources.add(AudioSource { sound }));
+        // Forget a failed attempt so the next one loads the track again
+        if self.source.is_none() {
+            self.key = None;
+        }
+    }
+
+    /// Loaded track for `key` - None while it is still loading, Some(None) if loading failed
+    fn get(&self, key: &K) -> Option<Option<Handle<AudioSource>>> {
+        if self.task.is_some() {
+            return None;
+        }
+        match &self.key {
+            Some(loaded) if loaded == key => Some(self.source.clone()),
+            Some(_) => None,
+            None => Some(None),
+        }
+    }
+}
+
+/// Music clock as seen by gameplay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MusicClock {
+    /// No music (missing file or track finished) - gameplay runs on frame time
+    Silent,
+    /// Music was started but is not audible yet
+    Pending,
+    /// Music is playing at the given position in milliseconds (of the track, not real time)
+    Playing(f64),
+}
+
+impl MusicPlayback {
+    /// Get the current music clock
+    pub fn clock(&self, instances: &Assets<AudioInstance>) -> MusicClock {
+        let Some(handle) = &self.instance else {
+            return if self.loading {
+                MusicClock::Pending
+            } else {
+                MusicClock::Silent
+            };
+        };
+
+        // The instance asset only exists once the audio backend picked up the play command
+        let Some(instance) = instances.get(handle) else {
+            return MusicClock::Pending;
+        };
+
+        match instance.state() {
+            PlaybackState::Playing { position } => {
+                MusicClock::Playing(position * 1000.0 * self.position_scale)
+            }
+            PlaybackState::Stopped | PlaybackState::Stopping { .. } => MusicClock::Silent,
+            _ => MusicClock::Pending,
+        }
+    }
+}
+
+fn start_music(
+    track: Res<MusicTrack>,
+    config: Res<MusicConfig>,
+    sources: Res<Assets<AudioSource>>,
+    mut playback: ResMut<MusicPlayback>,
+    mut cache: ResMut<MusicCache>,
+) {
+    *playback = MusicPlayback::default();
+
+    let Some(path) = &track.path else {
+        return;
+    };
+
+    // Decoding (and stretching) a whole song takes a while, so it runs in the
+    // background while the clock is held - unless the track is left from an earlier
+    // attempt. Beatmaps live outside the asset folder, so files are decoded directly
+    playback.loading = true;
+    if track.rate != 1.0 && config.preserve_pitch {
+        playback.position_scale = track.rate;
+
+        // Stretch the decoded track if there is one rather than decoding it again
+        let decoded = match cache.decoded.get(path) {
+            Some(Some(source)) => sources.get(&source).map(|source| source.sound.clone()),
+            _ => None,
+        };
+        let (path, rate) = (path.clone(), track.rate);
+        cache.stretched.load((path.clone(), rate), async move {
+            let mut sound = match decoded {
+                Some(sound) => sound,
+                None => decode(&path)?,
+            };
+            sound.frames = time_stretch(&sound.frames, sound.sample_rate, rate).into();
+            Some(sound)
+        });
+    } else {
+        let path = path.clone();
+        cache
+            .decoded
+            .load(path.clone(), async move { decode(&path) });
+    }
+}
+
+fn decode(path: &Path) -> Option<StaticSoundData> {
+    StaticSoundData::from_file(path)
+        .inspect_err(|err| warn!("Failed to load music {}: {}", path.display(), err))
+        .ok()
+}
+
+fn finish_loading_music(mut cache: ResMut<MusicCache>, mut sources: ResMut<Assets<AudioSource>>) {
+    cache.decoded.poll(&mut sources);
+    cache.stretched.poll(&mut sources);
+}
+
+/// Start the track once it is loaded (and gameplay isn't paused)
+fn start_loaded_music(
+    track: Res<MusicTrack>,
+    config: Res<MusicConfig>,
+    cache: Res<MusicCache>,
+    audio: Res<Audio>,
+    mut playback: ResMut<MusicPlayback>,
+) {
+    let Some(path) = &track.path else {
+        return;
+    };
+    let loaded = if track.rate != 1.0 && config.preserve_pitch {
+        // Stretched audio already runs at the rate
+        cache
+            .stretched
+            .get(&(path.clone(), track.rate))
+            .map(|source| (source, 1.0))
+    } else {
+        // Kira resamples the track to play it at the rate
+        cache.decoded.get(path).map(|source| (source, track.rate))
+    };
+    let Some((source, playback_rate)) = loaded else {
+        return;
+    };
+
+    playback.loading = false;
+    match source {
+        Some(source) => play_track(&audio, source, playback_rate, &track, &mut playback),
+        // Play without music, like a missing track
+        None => playback.position_scale = 1.0,
+    }
+}
+
+fn play_track(
+    audio: &Audio,
+    source: Handle<AudioSource>,
+    playback_rate: f64,
+    track: &MusicTrack,
+    playback: &mut MusicPlayback,
+) {
+    playback.instance = Some(
+        audio
+            .play(source)
+            .with_playback_rate(playback_rate)
+            .start_from(track.start_ms / 1000.0 / playback.position_scale)
+            .handle(),
+    );
+}
+
+fn stop_music(mut playback: ResMut<MusicPlayback>, mut instances: ResMut<Assets<AudioInstance>>) {
+    if let Some(handle) = playback.instance.take()
+        && let Some(instance) = instances.get_mut(&handle)
+    {
+        instance.stop(AudioTween::default());
+    }
+}
+
+fn pause_music(playback: Res<MusicPlayback>, mut instances: ResMut<Assets<AudioInstance>>) {
+    if let Some(handle) = &playback.instance
+        && let Some(instance) = instances.get_mut(handle)
+    {
+        instance.pause(AudioTween::default());
+    }
+}
+
+fn resume_music(playback: Res<MusicPlayback>, mut instances: ResMut<Assets<AudioInstance>>) {
+    if let Some(handle) = &playback.instance
+        && let Some(instance) = instances.get_mut(handle)
+    {
+        instance.resume(AudioTween::default());
+    }
+}
@@ -0,0 +1,150 @@
+//! Autoplay - perfect input generated from the hit objects
+//!
+//! The generated input is played back as a replay, so it goes through the same
+//! KeyState and judgement systems as a human play.
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, HitObject, Mod, REPLAY_VERSION, Replay, ReplayFrame, ScoreSummary};
+
+use crate::beatmap::{BeatmapLoadSet, CurrentBeatmap};
+use crate::mods::ActiveMods;
+use crate::note::CurrentHitObjects;
+use crate::playfield::PlayfieldConfig;
+use crate::replay::{ReplayPlayback, reset_replay};
+
+/// How long autoplay holds a tap note's key (ms)
+pub const AUTOPLAY_TAP_MS: f64 = 40.0;
+
+pub struct AutoplayPlugin;
+
+impl Plugin for AutoplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutoplayConfig>().add_systems(
+            OnEnter(GameState::Playing),
+            start_autoplay
+                .after(BeatmapLoadSet)
+                .before(reset_replay)
+                .run_if(|config: Res<AutoplayConfig>| config.enabled)
+                // Regenerated on every start, the objects change with the practice section
+                .run_if(|playback: Option<Res<ReplayPlayback>>| {
+                    playback.is_none_or(|playback| playback.is_autoplay())
+                }),
+        );
+    }
+}
+
+/// Autoplay preferences
+#[derive(Resource, Default)]
+pub struct AutoplayConfig {
+    /// Play the map automatically
+    pub enabled: bool,
+}
+
+/// Key change for one column
+struct KeyEvent {
+    time: f64,
+    column: u8,
+    pressed: bool,
+}
+
+/// Generate replay frames that hit every object perfectly
+///
+/// Taps are pressed at their time and released after `AUTOPLAY_TAP_MS` (or halfway
+/// to the next object in the column), holds are held until their end time.
+pub fn autoplay_frames(objects: &[HitObject]) -> Vec<ReplayFrame> {
+    let mut by_column: Vec<Vec<&HitObject>> = Vec::new();
+    for obj in objects {
+        let column = obj.column() as usize;
+        if column >= 16 {
+            continue;
+        }
+        if by_column.len() <= column {
+            by_column.resize(column + 1, Vec::new());
+        }
+        by_column[column].push(obj);
+    }
+
+    let mut events = Vec::new();
+    for column in by_column.iter_mut() {
+        column.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        for (i, obj) in column.iter().enumerate() {
+            let release = if obj.is_hold() {
+                obj.end_time()
+            } else {
+                let next = column.get(i + 1).map(|next| next.time);
+                match next {
+                    Some(next) => (obj.time + AUTOPLAY_TAP_MS).min((obj.time + next) / 2.0),
+                    None => obj.time + AUTOPLAY_TAP_MS,
+                }
+            };
+
+            events.push(KeyEvent {
+                time: obj.time,
+                column: obj.column(),
+                pressed: true,
+            });
+            events.push(KeyEvent {
+                time: release,
+                column: obj.column(),
+                pressed: false,
+            });
+        }
+    }
+
+    // Releases first, so a column can be pressed again at the same time
+    events.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.pressed.cmp(&b.pressed)));
+
+    let mut frames: Vec<ReplayFrame> = Vec::new();
+    let mut keys = 0u16;
+    let mut changed = 0u16;
+    for event in events {
+        let bit = 1 << event.column;
+
+        // Merge simultaneous changes into one frame, unless the column already changed in it
+        let merge = frames
+            .last()
+            .is_some_and(|last| last.time == event.time && changed & bit == 0);
+        if !merge {
+            changed = 0;
+        }
+
+        if event.pressed {
+            keys |= bit;
+        } else {
+            keys &= !bit;
+        }
+        changed |= bit;
+
+        match frames.last_mut() {
+            Some(last) if merge => last.keys = keys,
+            _ => frames.push(ReplayFrame::new(event.time, keys)),
+        }
+    }
+
+    frames
+}
+
+fn start_autoplay(
+    mut commands: Commands,
+    hit_objects: Res<CurrentHitObjects>,
+    playfield: Res<PlayfieldConfig>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    active_mods: Res<ActiveMods>,
+) {
+    let mut mods = active_mods.0.clone();
+    mods.insert(Mod::Autoplay);
+
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        map_hash: beatmap.map(|b| b.hash.clone()).unwrap_or_default(),
+        key_count: playfield.key_count,
+        mods: mods.to_acronyms(),
+        score: ScoreSummary::default(),
+        performance: 0.0,
+        timestamp: 0,
+        frames: autoplay_frames(&hit_objects.objects),
+    };
+
+    commands.insert_resource(ReplayPlayback::new(replay));
+}
@@ -0,0 +1,116 @@
+//! Beatmap loading - configures gameplay from the selected map
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, HitWindows, ScrollCurve, ZuchsyaMap};
+
+use crate::audio::MusicTrack;
+use crate::health::HealthState;
+use crate::input::{KeyBindings, KeyState};
+use crate::judgement::{JudgementConfig, ScoreState};
+use crate::mods::ActiveMods;
+use crate::note::CurrentHitObjects;
+use crate::playfield::PlayfieldConfig;
+
+pub struct BeatmapPlugin;
+
+impl Plugin for BeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            apply_beatmap.in_set(BeatmapLoadSet),
+        );
+    }
+}
+
+/// System set applying the current beatmap when entering gameplay
+///
+/// Resets run before this set, anything spawned from the beatmap runs after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BeatmapLoadSet;
+
+/// Beatmap selected for play
+#[derive(Resource)]
+pub struct CurrentBeatmap {
+    pub map: ZuchsyaMap,
+    /// Path of the .zuchsya file (assets are resolved relative to it)
+    pub path: PathBuf,
+    /// Content hash of the beatmap (see `ZuchsyaMap::content_hash`), identifies it in replays
+    pub hash: String,
+}
+
+impl CurrentBeatmap {
+    pub fn new(map: ZuchsyaMap, path: PathBuf, hash: String) -> Self {
+        Self { map, path, hash }
+    }
+
+    /// Load a beatmap file for play
+    pub fn load(path: &Path) -> Result<Self, zuchsya_core::BeatmapError> {
+        let map = ZuchsyaMap::load(path)?;
+        let hash = map.content_hash();
+        Ok(Self::new(map, path.to_path_buf(), hash))
+    }
+
+    /// Folder containing the beatmap file
+    pub fn folder(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Resolved audio file path (None if the map has no audio)
+    pub fn audio_path(&self) -> Option<PathBuf> {
+        let file = &self.map.audio.file;
+        (!file.is_empty()).then(|| self.folder().join(file))
+    }
+
+    /// Number of judgements in a full play (holds are judged at head and tail)
+    pub fn judgement_count(&self) -> u32 {
+        (self.map.note_count() + self.map.hold_count() * 2) as u32
+    }
+}
+
+/// Configure playfield, input, judgement and scoring for the current beatmap and mods
+pub fn apply_beatmap(
+    beatmap: Option<Res<CurrentBeatmap>>,
+    mods: Res<ActiveMods>,
+    mut playfield: ResMut<PlayfieldConfig>,
+    mut bindings: ResMut<KeyBindings>,
+    mut key_state: ResMut<KeyState>,
+    mut judgement: ResMut<JudgementConfig>,
+    mut hit_objects: ResMut<CurrentHitObjects>,
+    mut music: ResMut<MusicTrack>,
+    mut score: ResMut<ScoreState>,
+    mut health: ResMut<HealthState>,
+) {
+    let Some(beatmap) = beatmap else {
+        return;
+    };
+
+    let mods = &mods.0;
+    let difficulty = &mods.apply_difficulty(&beatmap.map.difficulty);
+
+    playfield.key_count = difficulty.keys;
+    *bindings = KeyBindings::for_key_count(difficulty.keys);
+    *key_state = KeyState::new(difficulty.keys);
+    judgement.hit_windows = HitWindows::new(difficulty.od as f64).with_rate(mods.rate());
+    health.drain_rate = difficulty.hp as f64;
+
+    hit_objects.objects = beatmap.map.hit_objects.clone();
+    mods.apply_lanes(&mut hit_objects.objects, difficulty.keys);
+    hit_objects.scroll_curve = ScrollCurve::new(&beatmap.map.scroll_velocities);
+    music.path = beatmap.audio_path();
+    music.rate = mods.rate();
+    music.start_ms = 0.0;
+    score.set_total_objects(beatmap.judgement_count());
+    score.set_score_multiplier(mods.score_multiplier());
+
+    info!(
+        "Loaded {} - {} [{}] ({}K, OD {}, HP {})",
+        beatmap.map.metadata.artist,
+        beatmap.map.metadata.title,
+        beatmap.map.metadata.difficulty_name,
+        difficulty.keys,
+        difficulty.od,
+        difficulty.hp
+    );
+}
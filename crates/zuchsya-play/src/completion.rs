@@ -0,0 +1,66 @@
+//! Map completion - moves to the results screen after the last hit object
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, PlayState};
+
+use crate::beatmap::BeatmapLoadSet;
+use crate::note::CurrentHitObjects;
+use crate::practice::PracticeRun;
+use crate::scroll::GameTime;
+
+/// Time after the last object ends before showing results (ms)
+pub const END_GRACE_MS: f64 = 2000.0;
+
+pub struct CompletionPlugin;
+
+impl Plugin for CompletionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapEnd>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                compute_map_end.after(BeatmapLoadSet),
+            )
+            .add_systems(Update, check_map_end.run_if(in_state(PlayState::Running)));
+    }
+}
+
+/// End of the current map
+#[derive(Resource, Default)]
+pub struct MapEnd {
+    /// End time of the last hit object in ms (None = no objects loaded)
+    pub last_object_ms: Option<f64>,
+}
+
+impl MapEnd {
+    /// Time at which gameplay finishes
+    pub fn finish_time(&self) -> Option<f64> {
+        self.last_object_ms.map(|t| t + END_GRACE_MS)
+    }
+}
+
+fn compute_map_end(hit_objects: Res<CurrentHitObjects>, mut map_end: ResMut<MapEnd>) {
+    map_end.last_object_ms = hit_objects
+        .objects
+        .iter()
+        .map(|obj| obj.end_time())
+        .reduce(f64::max);
+}
+
+fn check_map_end(
+    game_time: Res<GameTime>,
+    map_end: Res<MapEnd>,
+    practice: Option<Res<PracticeRun>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // A looping practice run restarts instead of finishing
+    if practice.is_some_and(|practice| practice.loop_end_ms.is_some()) {
+        return;
+    }
+
+    if map_end
+        .finish_time()
+        .is_some_and(|finish| game_time.current_ms >= finish)
+    {
+        next_state.set(GameState::Results);
+    }
+}
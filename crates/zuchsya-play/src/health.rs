@@ -0,0 +1,78 @@
+//! Health - HP gained and lost from judgements, fails the play at zero
+//! (or on the first mistake under Sudden Death / Perfect)
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, PlayState};
+
+use crate::beatmap::BeatmapLoadSet;
+use crate::judgement::JudgementEvent;
+use crate::mods::ActiveMods;
+use crate::replay::ReplayPlayback;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HealthState>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_health.before(BeatmapLoadSet),
+            )
+            .add_systems(Update, update_health.run_if(in_state(PlayState::Running)));
+    }
+}
+
+/// Current health
+#[derive(Resource)]
+pub struct HealthState {
+    /// Health (0.0 - 1.0)
+    pub hp: f64,
+    /// HP drain rate of the current map (0-10)
+    pub drain_rate: f64,
+}
+
+impl Default for HealthState {
+    fn default() -> Self {
+        Self {
+            hp: 1.0,
+            drain_rate: 5.0,
+        }
+    }
+}
+
+impl HealthState {
+    /// Apply a judgement's health change
+    pub fn apply(&mut self, result: zuchsya_core::HitResult) {
+        self.hp = (self.hp + result.health_increase(self.drain_rate)).clamp(0.0, 1.0);
+    }
+
+    /// Check if health ran out
+    pub fn is_empty(&self) -> bool {
+        self.hp <= 0.0
+    }
+}
+
+fn reset_health(mut health: ResMut<HealthState>) {
+    health.hp = 1.0;
+}
+
+fn update_health(
+    mut events: MessageReader<JudgementEvent>,
+    mods: Res<ActiveMods>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut health: ResMut<HealthState>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    let mut failed = false;
+    for event in events.read() {
+        health.apply(event.result);
+        // Sudden Death / Perfect fail regardless of health
+        failed |= mods.0.fails_on(event.result);
+    }
+    failed |= health.is_empty() && !mods.0.no_fail();
+
+    // Replays are only saved for completed plays, so watching one never fails
+    if failed && playback.is_none() {
+        next_state.set(PlayState::Failed);
+    }
+}
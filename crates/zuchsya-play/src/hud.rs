@@ -3,7 +3,12 @@
 use bevy::prelude::*;
 use zuchsya_core::GameState;
 
+use crate::health::HealthState;
 use crate::judgement::{JudgementEvent, ScoreState};
+use crate::offset::OffsetChanged;
+
+/// How long the offset is shown after changing it (seconds)
+const OFFSET_TEXT_SECS: f32 = 2.0;
 
 pub struct HudPlugin;
 
@@ -12,7 +17,13 @@ impl Plugin for HudPlugin {
         app.add_systems(OnEnter(GameState::Playing), setup_hud)
             .add_systems(
                 Update,
-                (update_score_display, update_combo_display, show_judgement_text)
+                (
+                    update_score_display,
+                    update_combo_display,
+                    show_judgement_text,
+                    update_health_bar,
+                    show_offset_text,
+                )
                     .run_if(in_state(GameState::Playing)),
             )
             .add_systems(OnExit(GameState::Playing), cleanup_hud);
@@ -31,11 +42,19 @@ struct ComboText;
 #[derive(Component)]
 struct AccuracyText;
 
+#[derive(Component)]
+struct HealthBarFill;
+
 #[derive(Component)]
 struct JudgementText {
     timer: f32,
 }
 
+#[derive(Component)]
+struct OffsetText {
+    timer: f32,
+}
+
 fn setup_hud(mut commands: Commands) {
     // HUD Root container
     commands
@@ -100,6 +119,49 @@ fn setup_hud(mut commands: Commands) {
                 },
             ));
 
+            // Health bar (left, fills from the bottom)
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(20.0),
+                        bottom: Val::Px(100.0),
+                        width: Val::Px(12.0),
+                        height: Val::Px(300.0),
+                        align_items: AlignItems::FlexEnd,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.15)),
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        HealthBarFill,
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.5, 1.0, 0.5)),
+                    ));
+                });
+
+            // Local offset, shown briefly when nudged (top center)
+            parent.spawn((
+                OffsetText { timer: 0.0 },
+                Text::new(""),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(45.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+            ));
+
             // Judgement text (center)
             parent.spawn((
                 JudgementText { timer: 0.0 },
@@ -159,6 +221,22 @@ fn update_combo_display(
     }
 }
 
+fn update_health_bar(
+    health: Res<HealthState>,
+    mut query: Query<(&mut Node, &mut BackgroundColor), With<HealthBarFill>>,
+) {
+    if !health.is_changed() {
+        return;
+    }
+
+    for (mut node, mut color) in query.iter_mut() {
+        node.height = Val::Percent(health.hp as f32 * 100.0);
+        // Shift from green to red as health drops
+        let hp = health.hp as f32;
+        *color = BackgroundColor(Color::srgb(1.0 - hp * 0.5, 0.3 + hp * 0.7, 0.3 + hp * 0.2));
+    }
+}
+
 fn show_judgement_text(
     mut events: MessageReader<JudgementEvent>,
     mut query: Query<(&mut Text, &mut TextColor, &mut JudgementText)>,
@@ -196,6 +274,28 @@ fn show_judgement_text(
     }
 }
 
+fn show_offset_text(
+    mut events: MessageReader<OffsetChanged>,
+    mut query: Query<(&mut Text, &mut OffsetText)>,
+    time: Res<Time>,
+) {
+    for event in events.read() {
+        for (mut text, mut offset_text) in query.iter_mut() {
+            **text = format!("Local offset: {:+.0}ms", event.local_ms);
+            offset_text.timer = OFFSET_TEXT_SECS;
+        }
+    }
+
+    for (mut text, mut offset_text) in query.iter_mut() {
+        if offset_text.timer > 0.0 {
+            offset_text.timer -= time.delta_secs();
+            if offset_text.timer <= 0.0 {
+                **text = String::new();
+            }
+        }
+    }
+}
+
 fn cleanup_hud(mut commands: Commands, query: Query<Entity, With<HudRoot>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
@@ -3,6 +3,8 @@
 use bevy::prelude::*;
 use zuchsya_core::GameState;
 
+use crate::GameplaySet;
+
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
@@ -11,20 +13,13 @@ impl Plugin for InputPlugin {
             .insert_resource(KeyState::default())
             .add_systems(
                 Update,
-                (update_key_state, handle_escape).run_if(in_state(GameState::Playing)),
+                update_key_state
+                    .in_set(GameplaySet::Input)
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
 
-fn handle_escape(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut next_state: ResMut<NextState<GameState>>,
-) {
-    if keyboard.just_pressed(KeyCode::Escape) {
-        next_state.set(GameState::SongSelect);
-    }
-}
-
 /// Key bindings for each column
 #[derive(Resource)]
 pub struct KeyBindings {
@@ -82,6 +77,29 @@ impl KeyBindings {
                 KeyCode::KeyL,
                 KeyCode::Semicolon,
             ],
+            9 => vec![
+                KeyCode::KeyA,
+                KeyCode::KeyS,
+                KeyCode::KeyD,
+                KeyCode::KeyF,
+                KeyCode::Space,
+                KeyCode::KeyJ,
+                KeyCode::KeyK,
+                KeyCode::KeyL,
+                KeyCode::Semicolon,
+            ],
+            10 => vec![
+                KeyCode::KeyA,
+                KeyCode::KeyS,
+                KeyCode::KeyD,
+                KeyCode::KeyF,
+                KeyCode::KeyV,
+                KeyCode::KeyN,
+                KeyCode::KeyJ,
+                KeyCode::KeyK,
+                KeyCode::KeyL,
+                KeyCode::Semicolon,
+            ],
             _ => vec![KeyCode::KeyD, KeyCode::KeyF, KeyCode::KeyJ, KeyCode::KeyK],
         };
         Self { keys }
@@ -109,7 +127,7 @@ impl KeyState {
     }
 }
 
-fn update_key_state(
+pub fn update_key_state(
     keyboard: Res<ButtonInput<KeyCode>>,
     bindings: Res<KeyBindings>,
     mut state: ResMut<KeyState>,
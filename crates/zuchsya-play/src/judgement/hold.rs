@@ -156,15 +156,7 @@ pub fn process_hold_release(
         let tail_time_offset = head.end_time_ms - current_time;
         let lenient_offset = tail_time_offset / RELEASE_WINDOW_LENIENCE;
 
-        let should_judge = if just_released && state.is_holding {
-            true
-        } else if !is_key_held && current_time >= head.end_time_ms {
-            true
-        } else if is_key_held && current_time >= head.end_time_ms {
-            true
-        } else {
-            false
-        };
+        let should_judge = (just_released && state.is_holding) || current_time >= head.end_time_ms;
 
         if !should_judge {
             continue;
@@ -7,7 +7,10 @@ mod score;
 pub use score::ScoreState;
 
 use bevy::prelude::*;
-use zuchsya_core::{GameState, HitResult, HitWindows};
+use zuchsya_core::{GameState, HitResult, HitWindows, PlayState};
+
+use crate::GameplaySet;
+use crate::beatmap::BeatmapLoadSet;
 
 pub struct JudgementPlugin;
 
@@ -16,7 +19,10 @@ impl Plugin for JudgementPlugin {
         app.insert_resource(ScoreState::default())
             .insert_resource(JudgementConfig::default())
             .add_message::<JudgementEvent>()
-            .add_systems(OnEnter(GameState::Playing), reset_score)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_score.before(BeatmapLoadSet),
+            )
             .add_systems(
                 Update,
                 (
@@ -31,7 +37,8 @@ impl Plugin for JudgementPlugin {
                     hold::process_hold_release,
                     hold::cleanup_hold_notes,
                 )
-                    .run_if(in_state(GameState::Playing)),
+                    .in_set(GameplaySet::Judgement)
+                    .run_if(in_state(PlayState::Running)),
             );
     }
 }
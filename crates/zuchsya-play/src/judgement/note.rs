@@ -47,20 +47,20 @@ pub fn process_note_hits(
             }
         }
 
-        if let Some((entity, time_offset)) = closest_note {
-            if let Some(result) = config.hit_windows.result_for(time_offset) {
-                if let Ok((_, mut note)) = notes.get_mut(entity) {
-                    note.hit = true;
-                }
+        if let Some((entity, time_offset)) = closest_note
+            && let Some(result) = config.hit_windows.result_for(time_offset)
+        {
+            if let Ok((_, mut note)) = notes.get_mut(entity) {
+                note.hit = true;
+            }
 
-                score.add_judgement(result);
+            score.add_judgement(result);
 
-                events.write(JudgementEvent {
-                    result,
-                    column,
-                    time_offset,
-                });
-            }
+            events.write(JudgementEvent {
+                result,
+                column,
+                time_offset,
+            });
         }
     }
 }
@@ -1,7 +1,7 @@
 //! Score state and calculation (osu!mania scoring)
 
 use bevy::prelude::*;
-use zuchsya_core::HitResult;
+use zuchsya_core::{HitResult, ScoreRank, ScoreSummary};
 
 /// Current score state (osu!mania scoring)
 /// Max score = 1,000,000 = 150,000 (combo) + 850,000 (accuracy)
@@ -21,6 +21,7 @@ pub struct ScoreState {
 
     // For score calculation
     total_objects: u32,
+    score_multiplier: f64,
     objects_judged: u32,
     combo_score: f64,
     max_combo_score: f64,
@@ -40,6 +41,7 @@ impl Default for ScoreState {
             meh_count: 0,
             miss_count: 0,
             total_objects: 0,
+            score_multiplier: 1.0,
             objects_judged: 0,
             combo_score: 0.0,
             max_combo_score: 0.0,
@@ -54,6 +56,11 @@ impl ScoreState {
         self.total_objects = count;
     }
 
+    /// Set the mod score multiplier (call before gameplay starts)
+    pub fn set_score_multiplier(&mut self, multiplier: f64) {
+        self.score_multiplier = multiplier;
+    }
+
     pub fn add_judgement(&mut self, result: HitResult) {
         // Update counts
         match result {
@@ -99,7 +106,7 @@ impl ScoreState {
         self.combo = 0;
     }
 
-    /// Calculate current score (0 - 1,000,000)
+    /// Calculate current score (0 - 1,000,000 before the mod multiplier)
     pub fn score(&self) -> i64 {
         if self.objects_judged == 0 {
             return 0;
@@ -123,7 +130,40 @@ impl ScoreState {
         let acc_exp = 2.0 + 2.0 * self.accuracy;
         let accuracy_portion = 850_000.0 * self.accuracy.powf(acc_exp) * accuracy_progress;
 
-        (combo_portion + accuracy_portion) as i64
+        ((combo_portion + accuracy_portion) * self.score_multiplier) as i64
+    }
+
+    /// Get count for a specific result
+    pub fn count_for(&self, result: HitResult) -> u32 {
+        match result {
+            HitResult::Perfect => self.perfect_count,
+            HitResult::Great => self.great_count,
+            HitResult::Good => self.good_count,
+            HitResult::Ok => self.ok_count,
+            HitResult::Meh => self.meh_count,
+            HitResult::Miss => self.miss_count,
+        }
+    }
+
+    /// Calculate rank from current accuracy and judgements
+    pub fn rank(&self) -> ScoreRank {
+        let has_imperfect = self.good_count + self.ok_count + self.meh_count + self.miss_count > 0;
+        ScoreRank::from_accuracy(self.accuracy, has_imperfect)
+    }
+
+    /// Snapshot of the final result
+    pub fn summary(&self) -> ScoreSummary {
+        ScoreSummary {
+            score: self.score(),
+            accuracy: self.accuracy,
+            max_combo: self.max_combo,
+            perfect: self.perfect_count,
+            great: self.great_count,
+            good: self.good_count,
+            ok: self.ok_count,
+            meh: self.meh_count,
+            miss: self.miss_count,
+        }
     }
 
     pub fn total_notes(&self) -> u32 {
@@ -1,35 +1,106 @@
 //! Zuchsya Play - Gameplay systems
 
 use bevy::prelude::*;
+use zuchsya_core::{GameState, PlayState};
 
+pub mod audio;
+pub mod autoplay;
+pub mod beatmap;
+pub mod completion;
+pub mod health;
 pub mod input;
 pub mod judgement;
+pub mod mods;
 pub mod note;
+pub mod offset;
+pub mod pause;
 pub mod playfield;
+pub mod practice;
+pub mod profile;
+pub mod replay;
+pub mod scores;
 pub mod scroll;
+pub mod time_stretch;
 pub mod hud;
 
+pub use audio::{MusicClock, MusicConfig, MusicPlayback, MusicPlugin, MusicTrack};
+pub use autoplay::{AutoplayConfig, AutoplayPlugin};
+pub use beatmap::{BeatmapLoadSet, BeatmapPlugin, CurrentBeatmap};
+pub use completion::{CompletionPlugin, MapEnd};
+pub use health::{HealthPlugin, HealthState};
 pub use input::{InputPlugin, KeyBindings, KeyState};
 pub use judgement::{JudgementEvent, JudgementPlugin, ScoreState};
+pub use mods::{ActiveMods, ModsPlugin, SelectedMods};
 pub use note::{CurrentHitObjects, HoldNoteBody, HoldNoteHead, HoldNoteId, HoldNoteState, HoldNoteTail, Note, NotePlugin};
+pub use offset::{OffsetChanged, OffsetPlugin, Offsets};
+pub use pause::{PausePlugin, ResumeCountdown};
 pub use playfield::{Column, HitTarget, Playfield, PlayfieldConfig, PlayfieldPlugin};
+pub use practice::{PracticeConfig, PracticePlugin, PracticeRun};
+pub use profile::{CurrentProfile, ProfilePlugin};
+pub use replay::{LastReplay, ReplayPlayback, ReplayPlugin, ReplayRecorder};
+pub use scores::{LastScore, LocalScores, ScoresPlugin};
 pub use scroll::{GameTime, ScrollConfig, ScrollPlugin};
 pub use hud::HudPlugin;
 
+/// Per-frame gameplay ordering: advance the clock, read input, then judge
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameplaySet {
+    /// Advance GameTime
+    Clock,
+    /// Update KeyState (keyboard or replay)
+    Input,
+    /// Judge notes against the current input and time
+    Judgement,
+}
+
+/// Systems saving a finished play on entering results - results screens run after it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordPlaySet;
+
 /// Gameplay plugin - adds all gameplay systems
 pub struct PlayPlugin;
 
 impl Plugin for PlayPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(scroll::GameTime::default())
+        app.add_sub_state::<PlayState>()
+            .configure_sets(
+                Update,
+                (
+                    GameplaySet::Clock,
+                    GameplaySet::Input,
+                    GameplaySet::Judgement,
+                )
+                    .chain(),
+            )
+            .insert_resource(scroll::GameTime::default())
             .insert_resource(note::CurrentHitObjects::default())
             .add_plugins((
+                mods::ModsPlugin,
+                practice::PracticePlugin,
+                offset::OffsetPlugin,
+            ))
+            .add_plugins((
+                audio::MusicPlugin,
+                beatmap::BeatmapPlugin,
                 playfield::PlayfieldPlugin,
                 input::InputPlugin,
                 scroll::ScrollPlugin,
                 note::NotePlugin,
                 judgement::JudgementPlugin,
+                completion::CompletionPlugin,
+                health::HealthPlugin,
+                pause::PausePlugin,
+                replay::ReplayPlugin,
+                autoplay::AutoplayPlugin,
+                profile::ProfilePlugin,
+                scores::ScoresPlugin,
                 hud::HudPlugin,
-            ));
+            ))
+            .add_systems(OnEnter(GameState::Restarting), restart_play);
     }
 }
+
+/// Re-enter gameplay (state transitions to the same state don't run OnEnter/OnExit)
+fn restart_play(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
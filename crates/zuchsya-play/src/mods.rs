@@ -0,0 +1,68 @@
+//! Mods - the player's selection and the mods of the current play
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, Mod, Mods};
+
+use crate::beatmap::BeatmapLoadSet;
+use crate::replay::ReplayPlayback;
+
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedMods>()
+            .init_resource::<ActiveMods>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                resolve_mods.before(BeatmapLoadSet),
+            );
+    }
+}
+
+/// Mods picked in song select
+///
+/// Random is stored without a seed, every play rolls a new one.
+#[derive(Resource, Default)]
+pub struct SelectedMods(pub Mods);
+
+/// Mods of the current play (from the selection, or from the watched replay)
+#[derive(Resource, Default)]
+pub struct ActiveMods(pub Mods);
+
+/// Pick the mods for the play that is starting
+fn resolve_mods(
+    selected: Res<SelectedMods>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut active: ResMut<ActiveMods>,
+) {
+    active.0 = match &playback {
+        // Replays are played back with the mods (and Random seed) they were recorded with
+        Some(playback) => Mods::from_acronyms(&playback.replay.mods).unwrap_or_else(|err| {
+            warn!("Ignoring replay mods: {}", err);
+            Mods::new()
+        }),
+        None => {
+            let mut mods = selected.0.clone();
+            if mods.contains(&Mod::Random { seed: 0 }) {
+                mods.insert(Mod::Random {
+                    seed: random_seed(),
+                });
+            }
+            mods
+        }
+    };
+
+    if !active.0.is_empty() {
+        info!("Mods: {}", active.0.label());
+    }
+}
+
+/// Seed for a new Random play
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
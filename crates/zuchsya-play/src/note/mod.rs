@@ -9,15 +9,20 @@ pub use types::*;
 use bevy::prelude::*;
 use zuchsya_core::GameState;
 
+use crate::beatmap::BeatmapLoadSet;
+
 pub struct NotePlugin;
 
 impl Plugin for NotePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), spawn::spawn_notes)
-            .add_systems(
-                Update,
-                systems::update_note_positions.run_if(in_state(GameState::Playing)),
-            )
-            .add_systems(OnExit(GameState::Playing), systems::cleanup_notes);
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn::spawn_notes.after(BeatmapLoadSet),
+        )
+        .add_systems(
+            Update,
+            systems::update_note_positions.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnExit(GameState::Playing), systems::cleanup_notes);
     }
 }
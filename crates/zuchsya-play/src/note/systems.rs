@@ -2,6 +2,7 @@
 
 use bevy::prelude::*;
 
+use crate::mods::ActiveMods;
 use crate::playfield::HIT_TARGET_Y;
 use crate::scroll::{GameTime, ScrollConfig};
 
@@ -10,6 +11,8 @@ use super::types::*;
 pub fn update_note_positions(
     game_time: Res<GameTime>,
     scroll_config: Res<ScrollConfig>,
+    mods: Res<ActiveMods>,
+    hit_objects: Res<CurrentHitObjects>,
     mut notes: Query<(&Note, &mut Transform), Without<HoldNoteHead>>,
     mut hold_heads: Query<(&HoldNoteHead, &mut Transform), Without<Note>>,
     mut hold_bodies: Query<
@@ -23,30 +26,33 @@ pub fn update_note_positions(
 ) {
     const PLAYFIELD_HEIGHT: f32 = 600.0;
 
+    // Positions are measured along the SV curve, so distance reflects scroll speed changes.
+    // Scroll speed is in real time, so it doesn't change with the rate.
+    let curve = &hit_objects.scroll_curve;
+    let rate = mods.0.rate();
+    let current_position = scroll_config.scroll_position(curve, game_time.current_ms);
+    let y_for = |time_ms: f64| {
+        let distance = (scroll_config.scroll_position(curve, time_ms) - current_position) / rate;
+        HIT_TARGET_Y + scroll_config.time_to_y(distance, PLAYFIELD_HEIGHT)
+    };
+
     // Update regular notes
     for (note, mut transform) in notes.iter_mut() {
         if note.hit {
             continue;
         }
-        let time_diff = note.time_ms - game_time.current_ms;
-        let y_offset = scroll_config.time_to_y(time_diff, PLAYFIELD_HEIGHT);
-        transform.translation.y = HIT_TARGET_Y + y_offset;
+        transform.translation.y = y_for(note.time_ms);
     }
 
     // Update hold note heads
     for (hold_head, mut transform) in hold_heads.iter_mut() {
-        let time_diff = hold_head.start_time_ms - game_time.current_ms;
-        let y_offset = scroll_config.time_to_y(time_diff, PLAYFIELD_HEIGHT);
-        transform.translation.y = HIT_TARGET_Y + y_offset;
+        transform.translation.y = y_for(hold_head.start_time_ms);
     }
 
     // Update hold note bodies
     for (hold_body, mut transform, mut sprite) in hold_bodies.iter_mut() {
-        let start_time_diff = hold_body.start_time_ms - game_time.current_ms;
-        let end_time_diff = hold_body.end_time_ms - game_time.current_ms;
-
-        let start_y = HIT_TARGET_Y + scroll_config.time_to_y(start_time_diff, PLAYFIELD_HEIGHT);
-        let end_y = HIT_TARGET_Y + scroll_config.time_to_y(end_time_diff, PLAYFIELD_HEIGHT);
+        let start_y = y_for(hold_body.start_time_ms);
+        let end_y = y_for(hold_body.end_time_ms);
 
         let body_height = (start_y - end_y).abs();
         let center_y = (start_y + end_y) / 2.0;
@@ -59,9 +65,7 @@ pub fn update_note_positions(
 
     // Update hold note tails
     for (hold_tail, mut transform) in hold_tails.iter_mut() {
-        let time_diff = hold_tail.end_time_ms - game_time.current_ms;
-        let y_offset = scroll_config.time_to_y(time_diff, PLAYFIELD_HEIGHT);
-        transform.translation.y = HIT_TARGET_Y + y_offset;
+        transform.translation.y = y_for(hold_tail.end_time_ms);
     }
 }
 
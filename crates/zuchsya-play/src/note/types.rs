@@ -1,7 +1,7 @@
 //! Note component types
 
 use bevy::prelude::*;
-use zuchsya_core::HitObject;
+use zuchsya_core::{HitObject, ScrollCurve};
 
 /// Note constants
 pub const NOTE_HEIGHT: f32 = 20.0;
@@ -72,4 +72,6 @@ pub struct HoldNoteState {
 #[derive(Resource, Default)]
 pub struct CurrentHitObjects {
     pub objects: Vec<HitObject>,
+    /// Scroll position curve built from the beatmap's SV changes
+    pub scroll_curve: ScrollCurve,
 }
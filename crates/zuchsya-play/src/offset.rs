@@ -0,0 +1,119 @@
+//! Audio offset - shifts game time against the music clock, adjustable during play
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use zuchsya_core::{AudioOffsets, GameState, PlayState};
+
+use crate::beatmap::{BeatmapLoadSet, CurrentBeatmap};
+use crate::mods::ActiveMods;
+use crate::scroll::{GameTime, reset_game_time};
+
+/// File offsets are saved to
+pub const OFFSETS_FILE: &str = "offsets.json";
+
+/// Local offset change per key press during play (ms)
+pub const OFFSET_NUDGE_MS: f64 = 5.0;
+
+pub struct OffsetPlugin;
+
+impl Plugin for OffsetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<OffsetChanged>()
+            .add_systems(Startup, load_offsets)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                apply_offset.after(reset_game_time).after(BeatmapLoadSet),
+            )
+            .add_systems(
+                Update,
+                nudge_local_offset.run_if(in_state(PlayState::Running)),
+            );
+    }
+}
+
+/// Offsets of the local player
+#[derive(Resource, Default)]
+pub struct Offsets(pub AudioOffsets);
+
+impl Offsets {
+    /// Save to `OFFSETS_FILE`
+    pub fn save(&self) {
+        let path = Path::new(OFFSETS_FILE);
+        if let Err(err) = self.0.save(path) {
+            warn!("Failed to save offsets {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// The current map's local offset was changed during play
+#[derive(Message, Debug, Clone, Copy)]
+pub struct OffsetChanged {
+    /// New local offset (ms)
+    pub local_ms: f64,
+}
+
+fn load_offsets(mut commands: Commands) {
+    let path = Path::new(OFFSETS_FILE);
+    let offsets = if path.exists() {
+        AudioOffsets::load(path).unwrap_or_else(|err| {
+            warn!("Failed to load offsets {}: {}", path.display(), err);
+            AudioOffsets::default()
+        })
+    } else {
+        AudioOffsets::default()
+    };
+    commands.insert_resource(Offsets(offsets));
+}
+
+/// Offset of the map in map time (offsets are real time, the map may run at another rate)
+fn map_time_offset(offsets: &Offsets, map_hash: &str, mods: &ActiveMods) -> f64 {
+    offsets.0.total(map_hash) * mods.0.rate()
+}
+
+fn apply_offset(
+    offsets: Res<Offsets>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    mods: Res<ActiveMods>,
+    mut game_time: ResMut<GameTime>,
+) {
+    if let Some(beatmap) = beatmap {
+        game_time.offset_ms = map_time_offset(&offsets, &beatmap.hash, &mods);
+    }
+}
+
+/// Nudge the map's local offset with -/+ (the music is heard earlier/later)
+fn nudge_local_offset(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    mods: Res<ActiveMods>,
+    mut offsets: ResMut<Offsets>,
+    mut game_time: ResMut<GameTime>,
+    mut changed: MessageWriter<OffsetChanged>,
+) {
+    let Some(beatmap) = beatmap else {
+        return;
+    };
+
+    let mut step = 0.0;
+    if keyboard.just_pressed(KeyCode::Minus) {
+        step -= OFFSET_NUDGE_MS;
+    }
+    if keyboard.just_pressed(KeyCode::Equal) {
+        step += OFFSET_NUDGE_MS;
+    }
+    if step == 0.0 {
+        return;
+    }
+
+    let local_ms = offsets.0.local(&beatmap.hash) + step;
+    offsets.0.set_local(&beatmap.hash, local_ms);
+    offsets.save();
+
+    // Move game time right away, small changes would otherwise stay within the sync threshold
+    let offset_ms = map_time_offset(&offsets, &beatmap.hash, &mods);
+    game_time.current_ms -= offset_ms - game_time.offset_ms;
+    game_time.offset_ms = offset_ms;
+
+    changed.write(OffsetChanged { local_ms });
+}
@@ -0,0 +1,75 @@
+//! Pause - freezes gameplay and counts down before resuming
+
+use bevy::prelude::*;
+use zuchsya_core::PlayState;
+
+/// Countdown before play resumes after pausing (seconds)
+pub const RESUME_COUNTDOWN_SECS: f32 = 1.5;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResumeCountdown>()
+            .add_systems(OnEnter(PlayState::Resuming), start_countdown)
+            .add_systems(
+                Update,
+                (
+                    pause_on_escape.run_if(in_state(PlayState::Running)),
+                    resume_on_escape.run_if(in_state(PlayState::Paused)),
+                    (tick_countdown, cancel_countdown).run_if(in_state(PlayState::Resuming)),
+                ),
+            );
+    }
+}
+
+/// Time left before play resumes
+#[derive(Resource, Default)]
+pub struct ResumeCountdown {
+    /// Remaining seconds
+    pub remaining: f32,
+}
+
+fn pause_on_escape(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(PlayState::Paused);
+    }
+}
+
+fn resume_on_escape(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(PlayState::Resuming);
+    }
+}
+
+fn start_countdown(mut countdown: ResMut<ResumeCountdown>) {
+    countdown.remaining = RESUME_COUNTDOWN_SECS;
+}
+
+fn tick_countdown(
+    time: Res<Time>,
+    mut countdown: ResMut<ResumeCountdown>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    countdown.remaining -= time.delta_secs();
+    if countdown.remaining <= 0.0 {
+        countdown.remaining = 0.0;
+        next_state.set(PlayState::Running);
+    }
+}
+
+/// Pressing Escape during the countdown goes back to the pause menu
+fn cancel_countdown(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(PlayState::Paused);
+    }
+}
@@ -3,6 +3,8 @@
 use bevy::prelude::*;
 use zuchsya_core::GameState;
 
+use crate::beatmap::BeatmapLoadSet;
+
 /// Playfield constants (based on osu!mania)
 pub const COLUMN_WIDTH: f32 = 80.0;
 pub const COLUMN_SPACING: f32 = 2.0;
@@ -22,7 +24,10 @@ pub struct PlayfieldPlugin;
 impl Plugin for PlayfieldPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayfieldConfig::default())
-            .add_systems(OnEnter(GameState::Playing), setup_playfield)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                setup_playfield.after(BeatmapLoadSet),
+            )
             .add_systems(OnExit(GameState::Playing), cleanup_playfield);
     }
 }
@@ -0,0 +1,196 @@
+//! Practice - start a map part way through, optionally looping a section
+//!
+//! A practice run only contains the hit objects from its start time (up to the
+//! loop end), so earlier notes are never judged. Practice runs aren't recorded:
+//! no replay, score or performance is saved.
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, HitResult, PlayState};
+
+use crate::audio::MusicTrack;
+use crate::beatmap::{BeatmapLoadSet, CurrentBeatmap, apply_beatmap};
+use crate::judgement::{JudgementConfig, ScoreState};
+use crate::note::CurrentHitObjects;
+use crate::replay::ReplayPlayback;
+use crate::scroll::{GameTime, reset_game_time};
+
+/// Time before the practice start at which play begins (ms)
+pub const PRACTICE_LEAD_IN_MS: f64 = 2000.0;
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeConfig>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    start_practice.before(BeatmapLoadSet),
+                    apply_practice
+                        .in_set(BeatmapLoadSet)
+                        .after(apply_beatmap)
+                        .after(reset_game_time)
+                        .run_if(resource_exists::<PracticeRun>),
+                ),
+            )
+            .add_systems(
+                Update,
+                restart_loop
+                    .run_if(in_state(PlayState::Running))
+                    .run_if(resource_exists::<PracticeRun>),
+            );
+    }
+}
+
+/// Practice section picked in song select or the pause menu
+#[derive(Resource, Default, Clone)]
+pub struct PracticeConfig {
+    /// Content hash of the beatmap the section belongs to
+    pub map_hash: String,
+    /// Start time in ms (None = play from the beginning)
+    pub start_ms: Option<f64>,
+    /// Loop back to the start at this time (ms)
+    pub loop_end_ms: Option<f64>,
+}
+
+impl PracticeConfig {
+    /// Whether practice is set up for a beatmap
+    pub fn is_active_for(&self, map_hash: &str) -> bool {
+        self.map_hash == map_hash && (self.start_ms.is_some() || self.loop_end_ms.is_some())
+    }
+
+    /// Start time, 0 if only a loop end is set
+    pub fn start(&self) -> f64 {
+        self.start_ms.unwrap_or(0.0)
+    }
+
+    /// Set the start for a beatmap, dropping a loop end that would come before it
+    pub fn set_start(&mut self, map_hash: &str, start_ms: f64) {
+        self.select_map(map_hash);
+        self.start_ms = Some(start_ms.max(0.0));
+        if self.loop_end_ms.is_some_and(|end| end <= start_ms) {
+            self.loop_end_ms = None;
+        }
+    }
+
+    /// Set the loop end for a beatmap, None if it isn't after the start
+    pub fn set_loop_end(&mut self, map_hash: &str, loop_end_ms: Option<f64>) {
+        self.select_map(map_hash);
+        self.loop_end_ms = loop_end_ms.filter(|end| *end > self.start());
+    }
+
+    /// Forget the section of any previous beatmap
+    fn select_map(&mut self, map_hash: &str) {
+        if self.map_hash != map_hash {
+            *self = Self {
+                map_hash: map_hash.to_string(),
+                ..default()
+            };
+        }
+    }
+
+    /// Short description, e.g. "from 1:05.0, loop to 1:20.0"
+    pub fn label(&self) -> String {
+        let mut label = format!("from {}", format_practice_time(self.start()));
+        if let Some(end) = self.loop_end_ms {
+            label += &format!(", loop to {}", format_practice_time(end));
+        }
+        label
+    }
+}
+
+/// Format ms as m:ss.s
+fn format_practice_time(ms: f64) -> String {
+    let tenths = (ms.max(0.0) / 100.0).round() as u64;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+/// Section of the current practice run (absent for normal plays)
+#[derive(Resource, Clone)]
+pub struct PracticeRun {
+    pub start_ms: f64,
+    pub loop_end_ms: Option<f64>,
+    /// When the loop starts over - after the loop end, once every object in it is judged
+    pub restart_ms: Option<f64>,
+}
+
+/// Turn the practice config into a practice run if it belongs to the map being played
+fn start_practice(
+    mut commands: Commands,
+    config: Res<PracticeConfig>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    // A recorded replay covers the whole map (autoplay follows the practice section)
+    let watching_replay = playback.is_some_and(|playback| !playback.is_autoplay());
+
+    match beatmap {
+        Some(beatmap) if config.is_active_for(&beatmap.hash) && !watching_replay => {
+            commands.insert_resource(PracticeRun {
+                start_ms: config.start(),
+                loop_end_ms: config.loop_end_ms,
+                restart_ms: config.loop_end_ms,
+            });
+        }
+        _ => commands.remove_resource::<PracticeRun>(),
+    }
+}
+
+/// Cut the hit objects down to the section and start the clock before it
+///
+/// Objects starting in the section are kept whole, so a hold may run past the loop end.
+fn apply_practice(
+    mut practice: ResMut<PracticeRun>,
+    judgement: Res<JudgementConfig>,
+    mut hit_objects: ResMut<CurrentHitObjects>,
+    mut score: ResMut<ScoreState>,
+    mut music: ResMut<MusicTrack>,
+    mut game_time: ResMut<GameTime>,
+) {
+    let end = practice.loop_end_ms.unwrap_or(f64::INFINITY);
+    hit_objects
+        .objects
+        .retain(|obj| obj.time >= practice.start_ms && obj.time < end);
+
+    let judgements = hit_objects
+        .objects
+        .iter()
+        .map(|obj| if obj.is_hold() { 2 } else { 1 })
+        .sum();
+    score.set_total_objects(judgements);
+
+    // Late objects are judged up to the miss window after they end
+    let miss_window = judgement.hit_windows.window_for(HitResult::Miss);
+    let last_judged = hit_objects
+        .objects
+        .iter()
+        .map(|obj| obj.end_time() + miss_window)
+        .fold(f64::NEG_INFINITY, f64::max);
+    practice.restart_ms = practice.loop_end_ms.map(|end| end.max(last_judged));
+
+    let lead_in_start = (practice.start_ms - PRACTICE_LEAD_IN_MS).max(0.0);
+    music.start_ms = lead_in_start;
+    game_time.current_ms = lead_in_start;
+
+    info!(
+        "Practice from {:.0}ms{}",
+        practice.start_ms,
+        practice
+            .loop_end_ms
+            .map_or(String::new(), |end| format!(", looping at {:.0}ms", end))
+    );
+}
+
+/// Start the section over once everything in it is judged
+fn restart_loop(
+    practice: Res<PracticeRun>,
+    game_time: Res<GameTime>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if practice
+        .restart_ms
+        .is_some_and(|restart| game_time.current_ms >= restart)
+    {
+        next_state.set(GameState::Restarting);
+    }
+}
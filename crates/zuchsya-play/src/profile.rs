@@ -0,0 +1,60 @@
+//! Player profile - best performance per map, updated after every recorded play
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, PlayerProfile};
+
+use crate::RecordPlaySet;
+use crate::replay::{LastReplay, ReplayPlayback, finish_recording};
+
+/// File the profile is saved to
+pub const PROFILE_FILE: &str = "profile.json";
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_profile).add_systems(
+            OnEnter(GameState::Results),
+            record_play
+                .in_set(RecordPlaySet)
+                .after(finish_recording)
+                .run_if(not(resource_exists::<ReplayPlayback>)),
+        );
+    }
+}
+
+/// Profile of the local player
+#[derive(Resource, Default)]
+pub struct CurrentProfile(pub PlayerProfile);
+
+fn load_profile(mut commands: Commands) {
+    let path = Path::new(PROFILE_FILE);
+    let profile = if path.exists() {
+        PlayerProfile::load(path).unwrap_or_else(|err| {
+            warn!("Failed to load profile {}: {}", path.display(), err);
+            PlayerProfile::default()
+        })
+    } else {
+        PlayerProfile::default()
+    };
+    commands.insert_resource(CurrentProfile(profile));
+}
+
+/// Add the play that just finished to the profile
+fn record_play(last_replay: Option<Res<LastReplay>>, mut profile: ResMut<CurrentProfile>) {
+    let Some(last_replay) = last_replay else {
+        return;
+    };
+    let replay = &last_replay.0;
+
+    if profile.0.record(&replay.map_hash, replay.performance) {
+        info!("New best performance: {:.2}pp", replay.performance);
+    }
+
+    let path = Path::new(PROFILE_FILE);
+    if let Err(err) = profile.0.save(path) {
+        warn!("Failed to save profile {}: {}", path.display(), err);
+    }
+}
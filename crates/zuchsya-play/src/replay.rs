@@ -0,0 +1,221 @@
+//! Replay recording and playback
+//!
+//! - Recording: key state changes are stored with their GameTime
+//! - Playback: recorded frames drive KeyState instead of the keyboard, and game time
+//!   is held at each frame's timestamp so every input is judged exactly when it was recorded
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, Mod, PlayState, REPLAY_VERSION, Replay, ReplayFrame, performance};
+
+use crate::beatmap::CurrentBeatmap;
+use crate::input::{KeyState, update_key_state};
+use crate::judgement::ScoreState;
+use crate::mods::ActiveMods;
+use crate::practice::PracticeRun;
+use crate::scroll::GameTime;
+use crate::{GameplaySet, RecordPlaySet};
+
+/// Folder replays are saved to
+pub const REPLAY_FOLDER: &str = "replays";
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .add_systems(OnEnter(GameState::Playing), reset_replay)
+            .add_systems(
+                Update,
+                (
+                    hold_at_next_frame
+                        .in_set(GameplaySet::Clock)
+                        .after(crate::scroll::update_scroll)
+                        .run_if(resource_exists::<ReplayPlayback>),
+                    play_replay_input
+                        .in_set(GameplaySet::Input)
+                        .after(update_key_state)
+                        .run_if(resource_exists::<ReplayPlayback>),
+                    record_input
+                        .in_set(GameplaySet::Input)
+                        .after(update_key_state)
+                        .run_if(not(resource_exists::<ReplayPlayback>)),
+                )
+                    .run_if(in_state(PlayState::Running)),
+            )
+            .add_systems(
+                OnEnter(GameState::Results),
+                finish_recording
+                    .in_set(RecordPlaySet)
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    // Practice runs never reach the leaderboard or profile
+                    .run_if(not(resource_exists::<PracticeRun>)),
+            );
+    }
+}
+
+/// Input recorded during the current play
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    pub frames: Vec<ReplayFrame>,
+    last_keys: u16,
+}
+
+/// Replay being watched - while present, gameplay input comes from it
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    next_frame: usize,
+    keys: u16,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_frame: 0,
+            keys: 0,
+        }
+    }
+
+    /// Whether the replay is generated autoplay input rather than a recorded play
+    pub fn is_autoplay(&self) -> bool {
+        self.replay
+            .mods
+            .iter()
+            .any(|m| m == Mod::Autoplay.acronym())
+    }
+
+    /// Time of the next frame that hasn't been applied yet
+    fn next_frame_time(&self) -> Option<f64> {
+        self.replay.frames.get(self.next_frame).map(|f| f.time)
+    }
+}
+
+/// Replay of the last recorded play (watchable from the results screen)
+#[derive(Resource)]
+pub struct LastReplay(pub Replay);
+
+/// Pack pressed keys into a replay bitmask
+fn key_mask(key_state: &KeyState) -> u16 {
+    key_state
+        .pressed
+        .iter()
+        .take(16)
+        .enumerate()
+        .filter(|(_, pressed)| **pressed)
+        .fold(0, |mask, (i, _)| mask | (1 << i))
+}
+
+pub fn reset_replay(
+    mut commands: Commands,
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    *recorder = ReplayRecorder::default();
+
+    match playback {
+        Some(mut playback) => {
+            playback.next_frame = 0;
+            playback.keys = 0;
+        }
+        // A new recording replaces the previous play's replay
+        None => commands.remove_resource::<LastReplay>(),
+    }
+}
+
+fn record_input(
+    key_state: Res<KeyState>,
+    game_time: Res<GameTime>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let keys = key_mask(&key_state);
+    if keys != recorder.last_keys {
+        recorder.last_keys = keys;
+        recorder
+            .frames
+            .push(ReplayFrame::new(game_time.current_ms, keys));
+    }
+}
+
+/// Don't let game time skip past the next replay frame, so it is applied at its own time
+fn hold_at_next_frame(playback: Res<ReplayPlayback>, mut game_time: ResMut<GameTime>) {
+    if let Some(time) = playback.next_frame_time()
+        && game_time.current_ms > time
+    {
+        game_time.current_ms = time;
+    }
+}
+
+fn play_replay_input(
+    game_time: Res<GameTime>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut key_state: ResMut<KeyState>,
+) {
+    let previous = playback.keys;
+
+    // At most one frame per update (game time is held at the next frame)
+    if let Some(frame) = playback.replay.frames.get(playback.next_frame).copied()
+        && frame.time <= game_time.current_ms
+    {
+        playback.keys = frame.keys;
+        playback.next_frame += 1;
+    }
+
+    let keys = playback.keys;
+    for column in 0..key_state.pressed.len().min(16) {
+        let was_pressed = previous & (1 << column) != 0;
+        let pressed = keys & (1 << column) != 0;
+        key_state.pressed[column] = pressed;
+        key_state.just_pressed[column] = pressed && !was_pressed;
+        key_state.just_released[column] = !pressed && was_pressed;
+    }
+}
+
+pub fn finish_recording(
+    mut commands: Commands,
+    mut recorder: ResMut<ReplayRecorder>,
+    key_state: Res<KeyState>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    score: Res<ScoreState>,
+    active_mods: Res<ActiveMods>,
+) {
+    let Some(beatmap) = beatmap else {
+        return;
+    };
+
+    let mods = active_mods.0.to_acronyms();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let summary = score.summary();
+    let star_rating = beatmap
+        .map
+        .difficulty_attributes(active_mods.0.rate())
+        .star_rating;
+    let performance = performance(star_rating, &summary, &mods);
+
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        map_hash: beatmap.hash.clone(),
+        key_count: key_state.pressed.len() as u8,
+        mods,
+        score: summary,
+        performance,
+        timestamp,
+        frames: std::mem::take(&mut recorder.frames),
+    };
+
+    let path = PathBuf::from(REPLAY_FOLDER).join(replay.file_name());
+    match replay.save(&path) {
+        Ok(()) => info!("Saved replay to {}", path.display()),
+        Err(err) => warn!("Failed to save replay {}: {}", path.display(), err),
+    }
+
+    commands.insert_resource(LastReplay(replay));
+}
@@ -0,0 +1,151 @@
+//! Local scores - every recorded play is saved with a reference to its replay
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, Mods, ScoreRecord, ScoreStore};
+
+use crate::RecordPlaySet;
+use crate::replay::{LastReplay, REPLAY_FOLDER, ReplayPlayback, finish_recording};
+
+/// File scores are saved to
+pub const SCORES_FILE: &str = "scores.jsonl";
+
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_scores)
+            .add_systems(OnEnter(GameState::Playing), reset_last_score)
+            .add_systems(
+                OnEnter(GameState::Results),
+                record_score
+                    .in_set(RecordPlaySet)
+                    .after(finish_recording)
+                    .run_if(not(resource_exists::<ReplayPlayback>)),
+            );
+    }
+}
+
+/// Scores of all maps
+#[derive(Resource, Default)]
+pub struct LocalScores(pub ScoreStore);
+
+/// The score that was just recorded, with the best score on the map before it
+#[derive(Resource)]
+pub struct LastScore {
+    pub record: ScoreRecord,
+    pub previous_best: Option<ScoreRecord>,
+}
+
+impl LastScore {
+    /// Whether the score beats every earlier score on the map
+    pub fn is_personal_best(&self) -> bool {
+        self.previous_best
+            .as_ref()
+            .is_none_or(|best| self.record.score.score > best.score.score)
+    }
+}
+
+fn load_scores(mut commands: Commands) {
+    let path = Path::new(SCORES_FILE);
+    let store = match ScoreStore::open(path) {
+        Ok(store) => {
+            if store.skipped_lines > 0 {
+                warn!(
+                    "Skipped {} unreadable scores in {}",
+                    store.skipped_lines,
+                    path.display()
+                );
+            }
+            store
+        }
+        Err(err) => {
+            warn!("Failed to load scores {}: {}", path.display(), err);
+            ScoreStore::default()
+        }
+    };
+    commands.insert_resource(LocalScores(store));
+}
+
+fn reset_last_score(mut commands: Commands) {
+    commands.remove_resource::<LastScore>();
+}
+
+/// Save the play that just finished
+fn record_score(
+    mut commands: Commands,
+    last_replay: Option<Res<LastReplay>>,
+    mut scores: ResMut<LocalScores>,
+) {
+    let Some(last_replay) = last_replay else {
+        return;
+    };
+    let replay = &last_replay.0;
+
+    let rate = Mods::from_acronyms(&replay.mods).map_or(1.0, |mods| mods.rate());
+    let replay_file = replay.file_name();
+    let record = ScoreRecord {
+        map_hash: replay.map_hash.clone(),
+        score: replay.score.clone(),
+        rank: replay.score.rank(),
+        mods: replay.mods.clone(),
+        rate,
+        performance: replay.performance,
+        timestamp: replay.timestamp,
+        replay: Path::new(REPLAY_FOLDER)
+            .join(&replay_file)
+            .exists()
+            .then_some(replay_file),
+    };
+
+    let previous_best = scores.0.personal_best(&record.map_hash).cloned();
+    if let Err(err) = scores.0.add(record.clone()) {
+        warn!("Failed to save score: {}", err);
+    }
+
+    commands.insert_resource(LastScore {
+        record,
+        previous_best,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use zuchsya_core::{ScoreRank, ScoreSummary};
+
+    use super::*;
+
+    fn record(score: i64) -> ScoreRecord {
+        ScoreRecord {
+            map_hash: "map".to_string(),
+            score: ScoreSummary {
+                score,
+                ..Default::default()
+            },
+            rank: ScoreRank::A,
+            mods: Vec::new(),
+            rate: 1.0,
+            performance: 0.0,
+            timestamp: 0,
+            replay: None,
+        }
+    }
+
+    fn is_personal_best(score: i64, previous_best: Option<i64>) -> bool {
+        LastScore {
+            record: record(score),
+            previous_best: previous_best.map(record),
+        }
+        .is_personal_best()
+    }
+
+    #[test]
+    fn personal_best() {
+        assert!(is_personal_best(500_000, None));
+        assert!(is_personal_best(900_001, Some(900_000)));
+        // Ties go to the earlier score
+        assert!(!is_personal_best(900_000, Some(900_000)));
+        assert!(!is_personal_best(800_000, Some(900_000)));
+    }
+}
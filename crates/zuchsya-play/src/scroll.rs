@@ -1,17 +1,40 @@
 //! Scroll system for notes
 
+use std::path::Path;
+
 use bevy::prelude::*;
-use zuchsya_core::GameState;
+use bevy_kira_audio::prelude::AudioInstance;
+use zuchsya_core::{GameState, PlayState, Preferences, ScrollCurve};
+
+use crate::GameplaySet;
+use crate::audio::{MusicClock, MusicPlayback};
+use crate::beatmap::BeatmapLoadSet;
+use crate::mods::ActiveMods;
+
+/// Max drift (ms) from the audio clock before game time snaps back to it
+const AUDIO_SYNC_THRESHOLD_MS: f64 = 40.0;
+
+/// File player preferences are saved to
+pub const PREFERENCES_FILE: &str = "preferences.json";
 
 pub struct ScrollPlugin;
 
 impl Plugin for ScrollPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ScrollConfig::default())
-            .add_systems(OnEnter(GameState::Playing), reset_game_time)
+            .add_systems(Startup, load_preferences)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_game_time.before(BeatmapLoadSet),
+            )
             .add_systems(
                 Update,
-                (update_scroll, adjust_scroll_speed).run_if(in_state(GameState::Playing)),
+                (
+                    update_scroll
+                        .in_set(GameplaySet::Clock)
+                        .run_if(in_state(PlayState::Running)),
+                    adjust_scroll_speed.run_if(in_state(GameState::Playing)),
+                ),
             );
     }
 }
@@ -23,6 +46,8 @@ pub struct ScrollConfig {
     pub speed: u8,
     /// Time range in ms (calculated from speed)
     pub time_range_ms: f64,
+    /// Scroll at constant speed, ignoring the beatmap's SV changes
+    pub ignore_scroll_velocity: bool,
 }
 
 impl Default for ScrollConfig {
@@ -40,6 +65,7 @@ impl ScrollConfig {
         Self {
             speed,
             time_range_ms,
+            ignore_scroll_velocity: false,
         }
     }
 
@@ -54,30 +80,106 @@ impl ScrollConfig {
         MAX_TIME_RANGE - t * (MAX_TIME_RANGE - MIN_TIME_RANGE)
     }
 
+    /// Save the player's scroll preferences to `PREFERENCES_FILE`
+    pub fn save_preferences(&self) {
+        let preferences = Preferences {
+            ignore_scroll_velocity: self.ignore_scroll_velocity,
+        };
+        let path = Path::new(PREFERENCES_FILE);
+        if let Err(err) = preferences.save(path) {
+            warn!("Failed to save preferences {}: {}", path.display(), err);
+        }
+    }
+
+    /// Get the scroll position for a time (in ms at 1.0x), applying SV unless ignored
+    pub fn scroll_position(&self, curve: &ScrollCurve, time_ms: f64) -> f64 {
+        if self.ignore_scroll_velocity {
+            time_ms
+        } else {
+            curve.position_at(time_ms)
+        }
+    }
+
     /// Get Y position for a note given its time relative to current time
     /// Returns position from hit target (negative = above, positive = below)
     pub fn time_to_y(&self, time_diff_ms: f64, playfield_height: f32) -> f32 {
         // time_diff_ms: positive = note is in future, negative = note is in past
+        // (a scroll position difference when SV is applied)
         let ratio = time_diff_ms / self.time_range_ms;
         (ratio * playfield_height as f64) as f32
     }
 }
 
+fn load_preferences(mut config: ResMut<ScrollConfig>) {
+    let path = Path::new(PREFERENCES_FILE);
+    if !path.exists() {
+        return;
+    }
+    match Preferences::load(path) {
+        Ok(preferences) => config.ignore_scroll_velocity = preferences.ignore_scroll_velocity,
+        Err(err) => warn!("Failed to load preferences {}: {}", path.display(), err),
+    }
+}
+
 /// Current game time (for scroll calculations)
 #[derive(Resource, Default)]
 pub struct GameTime {
     /// Current time in milliseconds
     pub current_ms: f64,
+    /// Audio offset in ms of map time - game time runs this far behind the audio clock
+    pub offset_ms: f64,
+    /// Last position reported by the audio clock
+    last_audio_ms: Option<f64>,
 }
 
-fn update_scroll(time: Res<Time>, mut game_time: ResMut<GameTime>) {
-    // Update game time
-    game_time.current_ms += time.delta_secs_f64() * 1000.0;
+impl GameTime {
+    /// Advance by frame time, following the audio clock whenever it reports a new position
+    ///
+    /// The audio position only updates once per audio buffer, so frame time is used
+    /// to interpolate between updates. Game time never steps backwards by less than
+    /// the sync threshold, which keeps notes from jittering.
+    pub fn advance(&mut self, delta_ms: f64, audio_ms: Option<f64>) {
+        self.current_ms += delta_ms;
+
+        let Some(audio_ms) = audio_ms else {
+            self.last_audio_ms = None;
+            return;
+        };
+        let audio_ms = audio_ms - self.offset_ms;
+
+        if self.last_audio_ms == Some(audio_ms) {
+            return;
+        }
+        self.last_audio_ms = Some(audio_ms);
+
+        let drift = self.current_ms - audio_ms;
+        if !(0.0..=AUDIO_SYNC_THRESHOLD_MS).contains(&drift) {
+            self.current_ms = audio_ms;
+        }
+    }
+}
+
+pub fn update_scroll(
+    time: Res<Time>,
+    music: Res<MusicPlayback>,
+    instances: Res<Assets<AudioInstance>>,
+    mods: Res<ActiveMods>,
+    mut game_time: ResMut<GameTime>,
+) {
+    // Game time is map time, which runs faster or slower than real time under rate mods
+    let delta_ms = time.delta_secs_f64() * 1000.0 * mods.0.rate();
+
+    match music.clock(&instances) {
+        // Hold time until the track is audible so the first notes line up
+        MusicClock::Pending => {}
+        MusicClock::Playing(audio_ms) => game_time.advance(delta_ms, Some(audio_ms)),
+        MusicClock::Silent => game_time.advance(delta_ms, None),
+    }
 }
 
 /// System to reset game time when entering Playing state
 pub fn reset_game_time(mut game_time: ResMut<GameTime>) {
-    game_time.current_ms = 0.0;
+    *game_time = GameTime::default();
 }
 
 /// Adjust scroll speed with F3/F4 keys
@@ -94,7 +196,9 @@ fn adjust_scroll_speed(keyboard: Res<ButtonInput<KeyCode>>, mut config: ResMut<S
     new_speed = new_speed.clamp(1, 40);
 
     if new_speed != config.speed as i32 {
-        *config = ScrollConfig::from_speed(new_speed as u8);
+        let speed = new_speed as u8;
+        config.speed = speed;
+        config.time_range_ms = ScrollConfig::calculate_time_range(speed);
         info!("Scroll speed: {} (time range: {:.0}ms)", config.speed, config.time_range_ms);
     }
 }
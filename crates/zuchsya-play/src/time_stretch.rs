@@ -0,0 +1,90 @@
+//! Time stretching - changes the speed of music without changing its pitch
+//!
+//! WSOLA (waveform similarity overlap-add): the output is built from overlapping,
+//! windowed segments of the input, which are read `rate` times faster than they
+//! are written. Each segment is shifted slightly to line up with the waveform of
+//! the previous one, which avoids the phasing of plain overlap-add.
+
+use bevy_kira_audio::prelude::Frame;
+
+/// Length of each segment
+const SEGMENT_MS: f64 = 40.0;
+/// How far a segment may be shifted to line up with the previous one
+const SEARCH_MS: f64 = 4.0;
+/// Segment shifts tried (every nth sample)
+const SEARCH_STEP: usize = 2;
+/// Samples compared when lining up segments (every nth sample)
+const CORRELATION_STEP: usize = 8;
+
+/// Stretch audio to play `rate` times as fast at the same pitch
+///
+/// The result is `1 / rate` times as long as the input.
+pub fn time_stretch(frames: &[Frame], sample_rate: u32, rate: f64) -> Vec<Frame> {
+    let segment = ((SEGMENT_MS / 1000.0 * sample_rate as f64) as usize).max(4) & !1;
+    let hop = segment / 2;
+    let search = (SEARCH_MS / 1000.0 * sample_rate as f64) as usize;
+    if rate == 1.0 || frames.len() < segment + 2 * search {
+        return frames.to_vec();
+    }
+
+    // Hann window - at half-segment hops the windows add up to exactly 1
+    let window: Vec<f32> = (0..segment)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / segment as f32).cos())
+        .collect();
+
+    let output_len = (frames.len() as f64 / rate) as usize;
+    let mut output = vec![Frame::ZERO; output_len + segment];
+
+    let mut previous: Option<usize> = None;
+    let mut position = 0;
+    while position < output_len {
+        let nominal = (position as f64 * rate) as usize;
+        let start = match previous {
+            Some(previous) => best_aligned(frames, previous + hop, nominal, search, hop),
+            None => nominal,
+        };
+        if start + segment > frames.len() {
+            break;
+        }
+
+        for (i, weight) in window.iter().enumerate() {
+            output[position + i] += frames[start + i] * *weight;
+        }
+        previous = Some(start);
+        position += hop;
+    }
+
+    output.truncate(output_len);
+    output
+}
+
+/// Start near `nominal` whose waveform best matches the one starting at `target`
+fn best_aligned(
+    frames: &[Frame],
+    target: usize,
+    nominal: usize,
+    search: usize,
+    len: usize,
+) -> usize {
+    let last = frames.len().saturating_sub(len);
+    if target > last {
+        return nominal;
+    }
+    let mono = |frame: &Frame| frame.left + frame.right;
+
+    let mut best = nominal;
+    let mut best_correlation = f32::NEG_INFINITY;
+    for candidate in
+        (nominal.saturating_sub(search)..=(nominal + search).min(last)).step_by(SEARCH_STEP)
+    {
+        let correlation: f32 = (0..len)
+            .step_by(CORRELATION_STEP)
+            .map(|i| mono(&frames[target + i]) * mono(&frames[candidate + i]))
+            .sum();
+        if correlation > best_correlation {
+            best_correlation = correlation;
+            best = candidate;
+        }
+    }
+    best
+}
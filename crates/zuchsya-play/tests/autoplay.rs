@@ -0,0 +1,213 @@
+//! Autoplay must be judged all Perfect with a full score, and follow the hit objects
+//! of every attempt
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use zuchsya_core::{
+    GameState, HitObject, HitResult, PlayState, REPLAY_VERSION, Replay, ReplayFrame, ScoreSummary,
+};
+use zuchsya_play::autoplay::autoplay_frames;
+use zuchsya_play::{
+    ActiveMods, AutoplayConfig, AutoplayPlugin, CurrentHitObjects, GameTime, GameplaySet,
+    JudgementEvent, JudgementPlugin, KeyState, NotePlugin, PlayfieldConfig, ReplayPlayback,
+    ReplayPlugin, ScoreState, ScrollConfig,
+};
+
+/// Game time advanced per update (ms)
+const STEP_MS: f64 = 1.0;
+
+/// Taps, holds and chords, including a hold overlapping taps in other columns
+fn test_map() -> Vec<HitObject> {
+    vec![
+        HitObject::note(0, 1000.0),
+        HitObject::note(1, 1100.0),
+        HitObject::note(2, 1200.0),
+        HitObject::note(3, 1300.0),
+        // Chords
+        HitObject::note(0, 1500.0),
+        HitObject::note(3, 1500.0),
+        HitObject::note(1, 1700.0),
+        HitObject::note(2, 1700.0),
+        // Jack
+        HitObject::note(0, 1900.0),
+        HitObject::note(0, 1950.0),
+        // Holds, with taps while they're held
+        HitObject::hold(0, 2200.0, 600.0),
+        HitObject::note(2, 2400.0),
+        HitObject::note(3, 2500.0),
+        HitObject::hold(1, 2600.0, 300.0),
+        // Hold chord, then a tap right after the holds end
+        HitObject::hold(2, 3200.0, 400.0),
+        HitObject::hold(3, 3200.0, 400.0),
+        HitObject::note(2, 3700.0),
+    ]
+}
+
+fn advance_clock(mut game_time: ResMut<GameTime>) {
+    game_time.current_ms += STEP_MS;
+}
+
+#[derive(Resource, Default)]
+struct Judgements(Vec<HitResult>);
+
+fn collect_judgements(
+    mut events: MessageReader<JudgementEvent>,
+    mut judgements: ResMut<Judgements>,
+) {
+    judgements.0.extend(events.read().map(|event| event.result));
+}
+
+/// Headless gameplay with the clock advanced by `STEP_MS` per update
+fn gameplay_app(objects: Vec<HitObject>) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_state::<GameState>()
+        .add_sub_state::<PlayState>()
+        .configure_sets(
+            Update,
+            (
+                GameplaySet::Clock,
+                GameplaySet::Input,
+                GameplaySet::Judgement,
+            )
+                .chain(),
+        )
+        .insert_resource(GameTime::default())
+        .insert_resource(CurrentHitObjects {
+            objects,
+            ..default()
+        })
+        .insert_resource(PlayfieldConfig::default())
+        .insert_resource(ScrollConfig::default())
+        .insert_resource(ActiveMods::default())
+        .insert_resource(KeyState::new(4))
+        .init_resource::<Judgements>()
+        .add_plugins((NotePlugin, JudgementPlugin, ReplayPlugin))
+        .add_systems(
+            Update,
+            (
+                advance_clock
+                    .in_set(GameplaySet::Clock)
+                    .run_if(in_state(PlayState::Running)),
+                collect_judgements.after(GameplaySet::Judgement),
+            ),
+        );
+    app
+}
+
+fn enter_playing(app: &mut App) {
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+}
+
+fn restart(app: &mut App) {
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Restarting);
+    app.update();
+    enter_playing(app);
+}
+
+fn playback_frames(app: &App) -> Vec<ReplayFrame> {
+    app.world()
+        .resource::<ReplayPlayback>()
+        .replay
+        .frames
+        .clone()
+}
+
+#[test]
+fn autoplay_is_all_perfect() {
+    let objects = test_map();
+    let judgement_count: u32 = objects
+        .iter()
+        .map(|obj| if obj.is_hold() { 2 } else { 1 })
+        .sum();
+    let end_ms = objects.iter().map(HitObject::end_time).fold(0.0, f64::max);
+
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        map_hash: String::new(),
+        key_count: 4,
+        mods: vec!["AT".into()],
+        score: ScoreSummary::default(),
+        performance: 0.0,
+        timestamp: 0,
+        frames: autoplay_frames(&objects),
+    };
+
+    let mut app = gameplay_app(objects);
+    app.insert_resource(ReplayPlayback::new(replay));
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ScoreState>()
+        .set_total_objects(judgement_count);
+
+    while app.world().resource::<GameTime>().current_ms < end_ms + 1000.0 {
+        app.update();
+    }
+
+    let judgements = &app.world().resource::<Judgements>().0;
+    assert_eq!(judgements.len(), judgement_count as usize);
+    assert!(
+        judgements
+            .iter()
+            .all(|result| *result == HitResult::Perfect),
+        "{judgements:?}"
+    );
+
+    let score = app.world().resource::<ScoreState>();
+    assert_eq!(score.perfect_count, judgement_count);
+    assert_eq!(score.max_combo, judgement_count);
+    assert_eq!(score.score(), 1_000_000);
+}
+
+#[test]
+fn autoplay_is_regenerated_on_restart() {
+    let mut app = gameplay_app(test_map());
+    app.insert_resource(AutoplayConfig { enabled: true })
+        .add_plugins(AutoplayPlugin);
+
+    enter_playing(&mut app);
+    assert_eq!(playback_frames(&app), autoplay_frames(&test_map()));
+
+    // A practice section (or any other change) cuts the objects before the next attempt
+    let section: Vec<HitObject> = test_map()
+        .into_iter()
+        .filter(|obj| obj.time >= 2200.0)
+        .collect();
+    app.world_mut().resource_mut::<CurrentHitObjects>().objects = section.clone();
+    restart(&mut app);
+    assert_eq!(playback_frames(&app), autoplay_frames(&section));
+}
+
+#[test]
+fn watched_replay_is_kept_on_restart() {
+    let frames = vec![ReplayFrame::new(1000.0, 1), ReplayFrame::new(1100.0, 0)];
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        map_hash: String::new(),
+        key_count: 4,
+        mods: Vec::new(),
+        score: ScoreSummary::default(),
+        performance: 0.0,
+        timestamp: 0,
+        frames: frames.clone(),
+    };
+
+    let mut app = gameplay_app(test_map());
+    app.insert_resource(AutoplayConfig { enabled: true })
+        .insert_resource(ReplayPlayback::new(replay))
+        .add_plugins(AutoplayPlugin);
+
+    enter_playing(&mut app);
+    assert_eq!(playback_frames(&app), frames);
+    restart(&mut app);
+    assert_eq!(playback_frames(&app), frames);
+}
@@ -0,0 +1,91 @@
+//! Command line tools, run instead of the game when a command is given
+//!
+//! - `zuchsya analyze <file.zuchsya>` - star rating and pattern analysis
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use zuchsya_core::ZuchsyaMap;
+
+/// Run the command in `args` (without the program name)
+///
+/// Returns None if there is no command and the game should start.
+pub fn run(args: &[String]) -> Option<Result<()>> {
+    let (command, args) = args.split_first()?;
+    Some(match command.as_str() {
+        "analyze" => analyze(args),
+        _ => Err(anyhow::anyhow!("Unknown command: {}", command)),
+    })
+}
+
+/// Print the difficulty and patterns of a beatmap
+fn analyze(args: &[String]) -> Result<()> {
+    let [path] = args else {
+        bail!("Usage: zuchsya analyze <file.zuchsya>");
+    };
+    let map =
+        ZuchsyaMap::load(Path::new(path)).with_context(|| format!("Failed to load {}", path))?;
+    let analysis = map.analyze_patterns();
+
+    let metadata = &map.metadata;
+    println!(
+        "{} - {} [{}] ({}K)",
+        metadata.artist, metadata.title, metadata.difficulty_name, map.difficulty.keys
+    );
+    println!("Stars: {:.2}", map.star_rating());
+    println!("Peak NPS: {:.0}", analysis.peak_nps);
+
+    println!("Patterns:");
+    if analysis.sections.is_empty() {
+        println!("  No clear patterns");
+    }
+    for section in &analysis.sections {
+        println!(
+            "  {} - {}  {}",
+            format_time(section.start),
+            format_time(section.end),
+            section.pattern
+        );
+    }
+
+    let rows: usize = analysis.chord_sizes.iter().sum();
+    println!("Chord sizes:");
+    for (size, &count) in analysis.chord_sizes.iter().enumerate().skip(1) {
+        if count > 0 {
+            println!(
+                "  {}: {} ({:.1}%)",
+                size,
+                count,
+                count as f64 * 100.0 / rows as f64
+            );
+        }
+    }
+
+    println!("Lanes:");
+    for (lane, share) in analysis.lane_balance().iter().enumerate() {
+        println!(
+            "  {}: {} ({:.1}%)",
+            lane + 1,
+            analysis.lane_counts[lane],
+            share
+        );
+    }
+
+    let nps: Vec<String> = analysis.nps.iter().map(|n| format!("{:.0}", n)).collect();
+    println!("NPS per second: {}", nps.join(" "));
+
+    Ok(())
+}
+
+/// Format ms as m:ss.mmm
+fn format_time(ms: f64) -> String {
+    let sign = if ms < 0.0 { "-" } else { "" };
+    let ms = ms.abs().round() as u64;
+    format!(
+        "{}{}:{:02}.{:03}",
+        sign,
+        ms / 60000,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
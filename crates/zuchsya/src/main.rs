@@ -2,13 +2,24 @@
 
 use bevy::prelude::*;
 use bevy::window::WindowResolution;
+use bevy_kira_audio::AudioPlugin;
 use zuchsya_core::GameState;
 use zuchsya_editor::EditorPlugin;
 use zuchsya_play::PlayPlugin;
 
+mod cli;
 mod ui;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        if let Err(err) = result {
+            eprintln!("{:#}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -18,6 +29,7 @@ fn main() {
             }),
             ..default()
         }))
+        .add_plugins(AudioPlugin)
         .init_state::<GameState>()
         .add_systems(Startup, setup)
         .add_plugins((ui::UiPlugin, PlayPlugin, EditorPlugin))
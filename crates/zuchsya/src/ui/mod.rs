@@ -4,7 +4,7 @@ use bevy::prelude::*;
 
 pub mod screens;
 
-use screens::{loading, main_menu, song_select};
+use screens::{fail, loading, main_menu, pause, results, settings, song_select};
 
 /// UI plugin
 pub struct UiPlugin;
@@ -15,6 +15,10 @@ impl Plugin for UiPlugin {
             loading::LoadingPlugin,
             main_menu::MainMenuPlugin,
             song_select::SongSelectPlugin,
+            results::ResultsPlugin,
+            fail::FailPlugin,
+            pause::PausePlugin,
+            settings::SettingsPlugin,
         ));
     }
 }
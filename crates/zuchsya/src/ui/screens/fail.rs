@@ -0,0 +1,140 @@
+//! Fail overlay (shown over the stopped playfield)
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, PlayState};
+
+pub struct FailPlugin;
+
+impl Plugin for FailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PlayState::Failed), setup_fail)
+            .add_systems(
+                Update,
+                (button_system, handle_input).run_if(in_state(PlayState::Failed)),
+            )
+            .add_systems(OnExit(PlayState::Failed), cleanup_fail);
+    }
+}
+
+#[derive(Component)]
+struct FailScreen;
+
+#[derive(Component)]
+enum FailButton {
+    Retry,
+    Quit,
+}
+
+const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.35, 0.75);
+
+fn setup_fail(mut commands: Commands) {
+    commands
+        .spawn((
+            FailScreen,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.3, 0.0, 0.0, 0.6)),
+            // Draw above the HUD
+            GlobalZIndex(10),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("FAILED"),
+                TextFont {
+                    font_size: 72.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.3, 0.3)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(50.0)),
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, "Retry", FailButton::Retry);
+                    spawn_button(parent, "Quit", FailButton::Quit);
+                });
+        });
+}
+
+fn spawn_button(parent: &mut ChildSpawnerCommands, text: &str, button_type: FailButton) {
+    parent
+        .spawn((
+            Button,
+            button_type,
+            Node {
+                width: Val::Px(200.0),
+                height: Val::Px(50.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(NORMAL_BUTTON),
+            BorderRadius::all(Val::Px(5.0)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &FailButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                match button {
+                    FailButton::Retry => next_state.set(GameState::Restarting),
+                    FailButton::Quit => next_state.set(GameState::SongSelect),
+                }
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+fn handle_input(keyboard: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::SongSelect);
+    }
+}
+
+fn cleanup_fail(mut commands: Commands, query: Query<Entity, With<FailScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,5 +1,9 @@
 //! Screen modules
 
+pub mod fail;
 pub mod loading;
 pub mod main_menu;
+pub mod pause;
+pub mod results;
+pub mod settings;
 pub mod song_select;
@@ -0,0 +1,231 @@
+//! Pause overlay and resume countdown
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, PlayState};
+use zuchsya_play::pause::{RESUME_COUNTDOWN_SECS, ResumeCountdown};
+use zuchsya_play::{CurrentBeatmap, GameTime, PracticeConfig};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PlayState::Paused), setup_pause)
+            .add_systems(Update, button_system.run_if(in_state(PlayState::Paused)))
+            .add_systems(OnExit(PlayState::Paused), cleanup_pause)
+            .add_systems(OnEnter(PlayState::Resuming), setup_countdown)
+            .add_systems(
+                Update,
+                update_countdown.run_if(in_state(PlayState::Resuming)),
+            )
+            .add_systems(OnExit(PlayState::Resuming), cleanup_countdown);
+    }
+}
+
+#[derive(Component)]
+struct PauseScreen;
+
+#[derive(Component)]
+struct CountdownText;
+
+#[derive(Component)]
+enum PauseButton {
+    Continue,
+    Retry,
+    /// Restart practicing from the current time
+    PracticeStart,
+    /// Loop back to the practice start at the current time
+    PracticeLoopEnd,
+    ClearPractice,
+    Quit,
+}
+
+const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.35, 0.75);
+
+fn setup_pause(
+    mut commands: Commands,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    practice: Res<PracticeConfig>,
+) {
+    let practice_label = beatmap
+        .filter(|beatmap| practice.is_active_for(&beatmap.hash))
+        .map(|_| format!("Practice: {}", practice.label()));
+
+    commands
+        .spawn((
+            PauseScreen,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            // Draw above the HUD
+            GlobalZIndex(10),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("PAUSED"),
+                TextFont {
+                    font_size: 72.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(50.0)),
+                    ..default()
+                },
+            ));
+
+            if let Some(label) = practice_label {
+                parent.spawn((
+                    Text::new(label),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.7, 0.7, 0.9)),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(20.0)),
+                        ..default()
+                    },
+                ));
+            }
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, "Continue", PauseButton::Continue);
+                    spawn_button(parent, "Retry", PauseButton::Retry);
+                    spawn_button(parent, "Practice From Here", PauseButton::PracticeStart);
+                    spawn_button(parent, "Loop To Here", PauseButton::PracticeLoopEnd);
+                    spawn_button(parent, "Clear Practice", PauseButton::ClearPractice);
+                    spawn_button(parent, "Quit", PauseButton::Quit);
+                });
+        });
+}
+
+fn spawn_button(parent: &mut ChildSpawnerCommands, text: &str, button_type: PauseButton) {
+    parent
+        .spawn((
+            Button,
+            button_type,
+            Node {
+                width: Val::Px(240.0),
+                height: Val::Px(50.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(NORMAL_BUTTON),
+            BorderRadius::all(Val::Px(5.0)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &PauseButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+    game_time: Res<GameTime>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    mut practice: ResMut<PracticeConfig>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                match button {
+                    PauseButton::Continue => next_play_state.set(PlayState::Resuming),
+                    PauseButton::Retry => next_game_state.set(GameState::Restarting),
+                    PauseButton::PracticeStart => {
+                        if let Some(beatmap) = &beatmap {
+                            practice.set_start(&beatmap.hash, game_time.current_ms);
+                            next_game_state.set(GameState::Restarting);
+                        }
+                    }
+                    PauseButton::PracticeLoopEnd => {
+                        if let Some(beatmap) = &beatmap {
+                            practice.set_loop_end(&beatmap.hash, Some(game_time.current_ms));
+                            next_game_state.set(GameState::Restarting);
+                        }
+                    }
+                    PauseButton::ClearPractice => *practice = PracticeConfig::default(),
+                    PauseButton::Quit => next_game_state.set(GameState::SongSelect),
+                }
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+fn cleanup_pause(mut commands: Commands, query: Query<Entity, With<PauseScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn setup_countdown(mut commands: Commands) {
+    commands.spawn((
+        CountdownText,
+        Text::new(""),
+        TextFont {
+            font_size: 96.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(50.0),
+            top: Val::Percent(35.0),
+            ..default()
+        },
+        GlobalZIndex(10),
+    ));
+}
+
+fn update_countdown(
+    countdown: Res<ResumeCountdown>,
+    mut query: Query<&mut Text, With<CountdownText>>,
+) {
+    // Count down in thirds of the resume delay: 3, 2, 1
+    let step = RESUME_COUNTDOWN_SECS / 3.0;
+    let number = (countdown.remaining / step).ceil().max(1.0) as u32;
+
+    for mut text in query.iter_mut() {
+        **text = number.to_string();
+    }
+}
+
+fn cleanup_countdown(mut commands: Commands, query: Query<Entity, With<CountdownText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,337 @@
+//! Results screen
+
+use bevy::prelude::*;
+use zuchsya_core::{GameState, HitResult, ScoreRank};
+use zuchsya_play::{
+    CurrentBeatmap, CurrentProfile, LastReplay, LastScore, PracticeRun, RecordPlaySet,
+    ReplayPlayback, ScoreState,
+};
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Results),
+            setup_results.after(RecordPlaySet),
+        )
+        .add_systems(
+            Update,
+            (button_system, handle_input).run_if(in_state(GameState::Results)),
+        )
+        .add_systems(OnExit(GameState::Results), cleanup_results);
+    }
+}
+
+#[derive(Component)]
+struct ResultsScreen;
+
+#[derive(Component)]
+enum ResultsButton {
+    Retry,
+    WatchReplay,
+    Back,
+}
+
+const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.35, 0.75);
+
+/// Judgements in display order (best first)
+const RESULT_ROWS: [(HitResult, Color); 6] = [
+    (HitResult::Perfect, Color::srgb(0.5, 1.0, 1.0)),
+    (HitResult::Great, Color::srgb(1.0, 1.0, 0.5)),
+    (HitResult::Good, Color::srgb(0.5, 1.0, 0.5)),
+    (HitResult::Ok, Color::srgb(0.5, 0.8, 0.5)),
+    (HitResult::Meh, Color::srgb(0.6, 0.6, 0.6)),
+    (HitResult::Miss, Color::srgb(1.0, 0.3, 0.3)),
+];
+
+fn rank_color(rank: ScoreRank) -> Color {
+    match rank {
+        ScoreRank::X => Color::srgb(1.0, 0.9, 0.4),
+        ScoreRank::S => Color::srgb(1.0, 0.8, 0.2),
+        ScoreRank::A => Color::srgb(0.4, 1.0, 0.4),
+        ScoreRank::B => Color::srgb(0.4, 0.6, 1.0),
+        ScoreRank::C => Color::srgb(0.8, 0.4, 1.0),
+        ScoreRank::D => Color::srgb(1.0, 0.3, 0.3),
+    }
+}
+
+fn setup_results(
+    mut commands: Commands,
+    score: Res<ScoreState>,
+    beatmap: Option<Res<CurrentBeatmap>>,
+    last_replay: Option<Res<LastReplay>>,
+    playback: Option<Res<ReplayPlayback>>,
+    profile: Option<Res<CurrentProfile>>,
+    last_score: Option<Res<LastScore>>,
+    practice: Option<Res<PracticeRun>>,
+) {
+    let rank = score.rank();
+    // The watched replay, or the play that was just recorded (autoplay earns nothing)
+    let replay = playback
+        .as_ref()
+        .map(|playback| &playback.replay)
+        .or(last_replay.as_ref().map(|last| &last.0))
+        .filter(|replay| !replay.mods.iter().any(|m| m == "AT"));
+
+    commands
+        .spawn((
+            ResultsScreen,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.08, 0.08, 0.12)),
+        ))
+        .with_children(|parent| {
+            // Beatmap info
+            if let Some(beatmap) = &beatmap {
+                let metadata = &beatmap.map.metadata;
+                parent.spawn((
+                    Text::new(format!(
+                        "{} - {} [{}]",
+                        metadata.artist, metadata.title, metadata.difficulty_name
+                    )),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                ));
+            }
+
+            // Rank
+            parent.spawn((
+                Text::new(rank.as_str()),
+                TextFont {
+                    font_size: 96.0,
+                    ..default()
+                },
+                TextColor(rank_color(rank)),
+                Node {
+                    margin: UiRect::vertical(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
+
+            // Score
+            parent.spawn((
+                Text::new(format!("{:07}", score.score())),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            // Personal best (only for the play that was just recorded)
+            if let Some(last_score) = last_score.as_ref().filter(|_| playback.is_none()) {
+                let (text, color) = if last_score.is_personal_best() {
+                    ("NEW PERSONAL BEST".to_string(), Color::srgb(1.0, 0.85, 0.3))
+                } else {
+                    let best = last_score
+                        .previous_best
+                        .as_ref()
+                        .map_or(0, |best| best.score.score);
+                    (
+                        format!("Personal best: {:07}", best),
+                        Color::srgb(0.6, 0.6, 0.6),
+                    )
+                };
+                parent.spawn((
+                    Text::new(text),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+
+            if practice.is_some() {
+                parent.spawn((
+                    Text::new("Practice run - not saved"),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                ));
+            }
+
+            // Accuracy and max combo
+            parent.spawn((
+                Text::new(format!(
+                    "{:.2}%  |  {}x max combo",
+                    score.accuracy * 100.0,
+                    score.max_combo
+                )),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
+
+            // Performance
+            if let Some(replay) = replay {
+                let mut text = format!("{:.0}pp", replay.performance);
+                if let Some(profile) = &profile {
+                    text += &format!("  |  Total {:.0}pp", profile.0.total_performance());
+                }
+                parent.spawn((
+                    Text::new(text),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.7, 0.7, 0.9)),
+                    Node {
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                ));
+            }
+
+            // Judgement breakdown
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::top(Val::Px(30.0)),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        min_width: Val::Px(300.0),
+                        row_gap: Val::Px(5.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.3)),
+                ))
+                .with_children(|list| {
+                    for (result, color) in RESULT_ROWS {
+                        list.spawn(Node {
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new(result.as_str()),
+                                TextFont {
+                                    font_size: 20.0,
+                                    ..default()
+                                },
+                                TextColor(color),
+                            ));
+                            row.spawn((
+                                Text::new(score.count_for(result).to_string()),
+                                TextFont {
+                                    font_size: 20.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    }
+                });
+
+            // Buttons
+            parent
+                .spawn(Node {
+                    margin: UiRect::top(Val::Px(30.0)),
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, "Retry", ResultsButton::Retry);
+                    if last_replay.is_some() {
+                        spawn_button(parent, "Watch Replay", ResultsButton::WatchReplay);
+                    }
+                    spawn_button(parent, "Back", ResultsButton::Back);
+                });
+        });
+}
+
+fn spawn_button(parent: &mut ChildSpawnerCommands, text: &str, button_type: ResultsButton) {
+    parent
+        .spawn((
+            Button,
+            button_type,
+            Node {
+                width: Val::Px(200.0),
+                height: Val::Px(50.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(NORMAL_BUTTON),
+            BorderRadius::all(Val::Px(5.0)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &ResultsButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut commands: Commands,
+    last_replay: Option<Res<LastReplay>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                match button {
+                    ResultsButton::Retry => {
+                        commands.remove_resource::<ReplayPlayback>();
+                        next_state.set(GameState::Playing);
+                    }
+                    ResultsButton::WatchReplay => {
+                        if let Some(last_replay) = &last_replay {
+                            commands.insert_resource(ReplayPlayback::new(last_replay.0.clone()));
+                            next_state.set(GameState::Playing);
+                        }
+                    }
+                    ResultsButton::Back => next_state.set(GameState::SongSelect),
+                }
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+fn handle_input(keyboard: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::SongSelect);
+    }
+}
+
+fn cleanup_results(mut commands: Commands, query: Query<Entity, With<ResultsScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
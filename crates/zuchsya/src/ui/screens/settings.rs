@@ -0,0 +1,353 @@
+//! Settings screen - scroll velocity preference, and the global audio offset with a
+//! calibration metronome
+//!
+//! The metronome is one looped bar of clicks. Taps are timed against the same
+//! audio clock gameplay follows, so their mean offset from the beat is exactly the
+//! offset gameplay needs.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::{
+    Audio, AudioControl, AudioInstance, AudioSource, AudioTween, Frame, PlaybackState,
+    StaticSoundData, StaticSoundSettings,
+};
+use zuchsya_core::{GameState, mean_tap_offset, tap_offset};
+use zuchsya_play::{GameTime, Offsets, ScrollConfig};
+
+/// Metronome tempo
+const CALIBRATION_BPM: f64 = 120.0;
+
+/// Beats per looped bar (the first one is accented)
+const CALIBRATION_BEATS: usize = 4;
+
+/// Taps needed before the measured offset can be used
+const MIN_CALIBRATION_TAPS: usize = 8;
+
+/// Sample rate of the generated metronome
+const METRONOME_SAMPLE_RATE: u32 = 44100;
+
+/// Length of a click (seconds)
+const CLICK_SECS: f32 = 0.03;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Settings),
+            (setup_settings, start_metronome),
+        )
+        .add_systems(
+            Update,
+            (
+                update_calibration,
+                handle_input,
+                update_texts,
+                update_scroll_velocity_text,
+            )
+                .chain()
+                .run_if(in_state(GameState::Settings)),
+        )
+        .add_systems(OnExit(GameState::Settings), cleanup_settings);
+    }
+}
+
+#[derive(Component)]
+struct SettingsScreen;
+
+#[derive(Component)]
+struct GlobalOffsetText;
+
+#[derive(Component)]
+struct ScrollVelocityText;
+
+#[derive(Component)]
+struct CalibrationText;
+
+/// Metronome and the taps recorded against it
+#[derive(Resource, Default)]
+struct Calibration {
+    instance: Option<Handle<AudioInstance>>,
+    /// Metronome time, following the audio clock like gameplay does
+    clock: GameTime,
+    /// Whether the metronome is audible yet
+    started: bool,
+    /// Offset of each tap from its beat (ms)
+    taps: Vec<f64>,
+}
+
+impl Calibration {
+    fn mean(&self) -> Option<f64> {
+        mean_tap_offset(&self.taps)
+    }
+}
+
+fn scroll_velocity_label(config: &ScrollConfig) -> String {
+    let state = if config.ignore_scroll_velocity {
+        "Off"
+    } else {
+        "On"
+    };
+    format!("Scroll velocity changes: {}", state)
+}
+
+fn global_offset_label(offsets: &Offsets) -> String {
+    format!("Global offset: {:+.0}ms", offsets.0.global_ms)
+}
+
+fn beat_interval_ms() -> f64 {
+    60000.0 / CALIBRATION_BPM
+}
+
+/// One bar of clicks, a higher click on the first beat
+fn metronome_sound() -> StaticSoundData {
+    let beat_frames = (beat_interval_ms() / 1000.0 * METRONOME_SAMPLE_RATE as f64) as usize;
+    let click_frames = (CLICK_SECS * METRONOME_SAMPLE_RATE as f32) as usize;
+
+    let mut frames = vec![Frame::ZERO; beat_frames * CALIBRATION_BEATS];
+    for beat in 0..CALIBRATION_BEATS {
+        let pitch = if beat == 0 { 1500.0 } else { 1000.0 };
+        for i in 0..click_frames {
+            let t = i as f32 / METRONOME_SAMPLE_RATE as f32;
+            let envelope = (-t / CLICK_SECS * 5.0).exp();
+            let sample = (t * pitch * std::f32::consts::TAU).sin() * envelope * 0.5;
+            frames[beat * beat_frames + i] = Frame::from_mono(sample);
+        }
+    }
+
+    StaticSoundData {
+        sample_rate: METRONOME_SAMPLE_RATE,
+        frames: Arc::from(frames),
+        settings: StaticSoundSettings::default(),
+        slice: None,
+    }
+}
+
+fn start_metronome(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    mut sources: ResMut<Assets<AudioSource>>,
+) {
+    let source = sources.add(AudioSource {
+        sound: metronome_sound(),
+    });
+    commands.insert_resource(Calibration {
+        instance: Some(audio.play(source).looped().handle()),
+        ..default()
+    });
+}
+
+fn setup_settings(mut commands: Commands, offsets: Res<Offsets>, scroll_config: Res<ScrollConfig>) {
+    commands
+        .spawn((
+            SettingsScreen,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(15.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.1, 0.1, 0.15)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("SETTINGS"),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                ScrollVelocityText,
+                Text::new(scroll_velocity_label(&scroll_config)),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent.spawn((
+                GlobalOffsetText,
+                Text::new(global_offset_label(&offsets)),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent.spawn((
+                Text::new("Tap SPACE on every click of the metronome"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                Node {
+                    margin: UiRect::top(Val::Px(30.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                CalibrationText,
+                Text::new(""),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.9)),
+            ));
+
+            parent.spawn((
+                Text::new(
+                    "V: Toggle scroll velocity | LEFT/RIGHT: Adjust offset | SPACE: Tap | ENTER: Use measured offset | BACKSPACE: Reset taps | ESC: Back",
+                ),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.5, 0.5, 0.5)),
+                Node {
+                    margin: UiRect::top(Val::Px(30.0)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Advance the metronome clock and time taps against it
+fn update_calibration(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    instances: Res<Assets<AudioInstance>>,
+    mut calibration: ResMut<Calibration>,
+) {
+    let position = calibration
+        .instance
+        .as_ref()
+        .and_then(|handle| instances.get(handle))
+        .and_then(|instance| match instance.state() {
+            PlaybackState::Playing { position } => Some(position * 1000.0),
+            _ => None,
+        });
+    let Some(position) = position else {
+        return;
+    };
+
+    let delta_ms = time.delta_secs_f64() * 1000.0;
+    calibration.clock.advance(delta_ms, Some(position));
+    calibration.started = true;
+
+    if keyboard.just_pressed(KeyCode::Space) {
+        let offset = tap_offset(calibration.clock.current_ms, beat_interval_ms());
+        calibration.taps.push(offset);
+    }
+}
+
+fn handle_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut calibration: ResMut<Calibration>,
+    mut offsets: ResMut<Offsets>,
+    mut scroll_config: ResMut<ScrollConfig>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyV) {
+        scroll_config.ignore_scroll_velocity = !scroll_config.ignore_scroll_velocity;
+        scroll_config.save_preferences();
+    }
+    if keyboard.just_pressed(KeyCode::ArrowLeft) {
+        offsets.0.global_ms -= 1.0;
+    }
+    if keyboard.just_pressed(KeyCode::ArrowRight) {
+        offsets.0.global_ms += 1.0;
+    }
+    if keyboard.just_pressed(KeyCode::Enter)
+        && calibration.taps.len() >= MIN_CALIBRATION_TAPS
+        && let Some(mean) = calibration.mean()
+    {
+        offsets.0.global_ms = mean.round();
+    }
+    if keyboard.just_pressed(KeyCode::Backspace) {
+        calibration.taps.clear();
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn update_texts(
+    calibration: Res<Calibration>,
+    offsets: Res<Offsets>,
+    mut global_texts: Query<&mut Text, (With<GlobalOffsetText>, Without<CalibrationText>)>,
+    mut calibration_texts: Query<&mut Text, (With<CalibrationText>, Without<GlobalOffsetText>)>,
+) {
+    if offsets.is_changed() {
+        for mut text in global_texts.iter_mut() {
+            **text = global_offset_label(&offsets);
+        }
+    }
+
+    if calibration.is_changed() {
+        let status = if !calibration.started {
+            "Starting metronome...".to_string()
+        } else {
+            match calibration.mean() {
+                Some(mean) if calibration.taps.len() >= MIN_CALIBRATION_TAPS => {
+                    format!(
+                        "Taps: {} | Measured: {:+.1}ms",
+                        calibration.taps.len(),
+                        mean
+                    )
+                }
+                _ => format!("Taps: {}/{}", calibration.taps.len(), MIN_CALIBRATION_TAPS),
+            }
+        };
+        for mut text in calibration_texts.iter_mut() {
+            **text = status.clone();
+        }
+    }
+}
+
+fn update_scroll_velocity_text(
+    scroll_config: Res<ScrollConfig>,
+    mut texts: Query<&mut Text, With<ScrollVelocityText>>,
+) {
+    if !scroll_config.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        **text = scroll_velocity_label(&scroll_config);
+    }
+}
+
+fn cleanup_settings(
+    mut commands: Commands,
+    query: Query<Entity, With<SettingsScreen>>,
+    calibration: Res<Calibration>,
+    mut instances: ResMut<Assets<AudioInstance>>,
+    offsets: Res<Offsets>,
+) {
+    if let Some(handle) = &calibration.instance
+        && let Some(instance) = instances.get_mut(handle)
+    {
+        instance.stop(AudioTween::default());
+    }
+    commands.remove_resource::<Calibration>();
+    offsets.save();
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,9 +1,48 @@
 //! Song selection screen
 
 use bevy::prelude::*;
-use std::path::PathBuf;
-use zuchsya_core::{GameState, ZuchsyaMap};
-use zuchsya_play::note::CurrentHitObjects;
+use std::path::{Path, PathBuf};
+use zuchsya_core::{
+    DOUBLE_TIME_RATE, Diagnostic, GameState, HALF_TIME_RATE, Mod, Mods, PatternAnalysis,
+    RATE_STEP, SET_ARCHIVE_EXTENSION, ZuchsyaMap, export_set, import_set,
+};
+use zuchsya_editor::{EditorBeatmap, severity_color};
+use zuchsya_play::{
+    AutoplayConfig, CurrentBeatmap, LocalScores, MusicConfig, PracticeConfig, ReplayPlayback,
+    SelectedMods,
+};
+
+/// Folder exported set archives are written to
+const EXPORT_FOLDER: &str = "exports";
+
+/// Appended to the extension of set archives that failed to import, so they aren't retried
+const FAILED_ARCHIVE_SUFFIX: &str = "failed";
+
+/// Diagnostics shown for the selected beatmap, the rest are summarized
+const SHOWN_DIAGNOSTICS: usize = 5;
+
+/// Scores shown in the selected beatmap's leaderboard
+const LEADERBOARD_SIZE: usize = 5;
+
+/// Mod toggle keys
+const MOD_KEYS: [(KeyCode, Mod); 9] = [
+    (KeyCode::F1, Mod::NoFail),
+    (KeyCode::KeyZ, Mod::Easy),
+    (KeyCode::KeyX, Mod::HardRock),
+    (KeyCode::KeyQ, Mod::Rate(HALF_TIME_RATE)),
+    (KeyCode::KeyW, Mod::Rate(DOUBLE_TIME_RATE)),
+    (KeyCode::KeyS, Mod::SuddenDeath),
+    (KeyCode::KeyP, Mod::Perfect),
+    (KeyCode::KeyM, Mod::Mirror),
+    // The seed is picked when the play starts
+    (KeyCode::KeyR, Mod::Random { seed: 0 }),
+];
+
+/// Step of the practice start time (ms)
+const PRACTICE_STEP_MS: f64 = 5000.0;
+
+/// Practice loop lengths to cycle through (ms)
+const PRACTICE_LOOP_LENGTHS_MS: [f64; 4] = [5000.0, 10000.0, 20000.0, 30000.0];
 
 pub struct SongSelectPlugin;
 
@@ -12,7 +51,19 @@ impl Plugin for SongSelectPlugin {
         app.init_resource::<BeatmapList>()
             .init_resource::<SelectedBeatmap>()
             .add_systems(OnEnter(GameState::SongSelect), (scan_beatmaps, setup_song_select).chain())
-            .add_systems(Update, handle_input.run_if(in_state(GameState::SongSelect)))
+            .add_systems(
+                Update,
+                (
+                    handle_input,
+                    toggle_mods,
+                    toggle_autoplay,
+                    adjust_practice,
+                    export_selected_set,
+                    open_editor,
+                    update_selection_details,
+                )
+                    .run_if(in_state(GameState::SongSelect)),
+            )
             .add_systems(OnExit(GameState::SongSelect), cleanup_song_select);
     }
 }
@@ -30,6 +81,42 @@ pub struct BeatmapEntry {
     pub title: String,
     pub artist: String,
     pub difficulty: String,
+    /// Star rating at normal speed
+    pub star_rating: f64,
+    /// Content hash, the key of the map's scores
+    pub hash: String,
+    /// Set the difficulty belongs to (see `ZuchsyaMap::set_id`)
+    pub set_id: String,
+    /// Patterns the chart is made of
+    pub patterns: PatternAnalysis,
+    /// Validation problems, including missing files
+    pub diagnostics: Vec<Diagnostic>,
+    /// Editor bookmarks (ms), practice can start at them
+    pub bookmarks: Vec<f64>,
+    /// End of the last hit object (ms)
+    pub duration: f64,
+}
+
+impl BeatmapEntry {
+    /// Whether the beatmap has problems that make it unplayable
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    /// List label, with a count of validation problems if there are any
+    fn label(&self) -> String {
+        let label = format!(
+            "{} - {} [{}] {:.2}*",
+            self.artist, self.title, self.difficulty, self.star_rating
+        );
+        let errors = self.diagnostics.iter().filter(|d| d.is_error()).count();
+        let warnings = self.diagnostics.len() - errors;
+        match (errors, warnings) {
+            (0, 0) => label,
+            (0, _) => format!("{} ({} warnings)", label, warnings),
+            _ => format!("{} ({} errors, {} warnings)", label, errors, warnings),
+        }
+    }
 }
 
 /// Currently selected beatmap index
@@ -44,48 +131,267 @@ struct SongSelectScreen;
 #[derive(Component)]
 struct BeatmapListItem(usize);
 
-/// Scan for .zuchsya files in the beatmaps folder
+#[derive(Component)]
+struct ModsText;
+
+fn mods_label(mods: &Mods, music_config: &MusicConfig) -> String {
+    if mods.is_empty() {
+        "Mods: None".to_string()
+    } else if mods.rate() != 1.0 && music_config.preserve_pitch {
+        format!("Mods: {} (pitch kept)", mods.label())
+    } else {
+        format!("Mods: {}", mods.label())
+    }
+}
+
+#[derive(Component)]
+struct AutoplayText;
+
+#[derive(Component)]
+struct PracticeText;
+
+fn practice_label(config: &PracticeConfig, entry: Option<&BeatmapEntry>) -> String {
+    match entry {
+        Some(entry) if config.is_active_for(&entry.hash) => {
+            format!("Practice: {}", config.label())
+        }
+        _ => "Practice: OFF".to_string(),
+    }
+}
+
+/// Text showing details of the selected beatmap
+#[derive(Component, Clone, Copy)]
+enum SelectionDetail {
+    Patterns,
+    Leaderboard,
+    Diagnostics,
+}
+
+/// Text and color of a detail of the selected beatmap
+fn selection_detail(
+    detail: SelectionDetail,
+    entry: Option<&BeatmapEntry>,
+    scores: &LocalScores,
+) -> (String, Color) {
+    match detail {
+        SelectionDetail::Patterns => (patterns_text(entry), Color::srgb(0.7, 0.7, 0.8)),
+        SelectionDetail::Leaderboard => {
+            (leaderboard_text(entry, scores), Color::srgb(0.8, 0.8, 0.8))
+        }
+        SelectionDetail::Diagnostics => diagnostics_text(entry),
+    }
+}
+
+/// Pattern summary of the selected beatmap
+fn patterns_text(entry: Option<&BeatmapEntry>) -> String {
+    entry
+        .map(|entry| entry.patterns.summary())
+        .unwrap_or_default()
+}
+
+/// Local leaderboard of the selected beatmap
+fn leaderboard_text(entry: Option<&BeatmapEntry>, scores: &LocalScores) -> String {
+    let Some(entry) = entry else {
+        return String::new();
+    };
+    let leaderboard = scores.0.leaderboard(&entry.hash);
+    if leaderboard.is_empty() {
+        return "No local scores".to_string();
+    }
+
+    leaderboard
+        .iter()
+        .take(LEADERBOARD_SIZE)
+        .enumerate()
+        .map(|(i, record)| {
+            let mut line = format!(
+                "{}. {:07}  {:.2}%  {}  {}x",
+                i + 1,
+                record.score.score,
+                record.score.accuracy * 100.0,
+                record.rank.as_str(),
+                record.score.max_combo
+            );
+            if !record.mods.is_empty() {
+                let mods = Mods::from_acronyms(&record.mods)
+                    .map_or_else(|_| record.mods.join(" "), |mods| mods.label());
+                line += &format!("  +{}", mods);
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Diagnostics of the selected beatmap, and the color to show them in
+fn diagnostics_text(entry: Option<&BeatmapEntry>) -> (String, Color) {
+    let Some(entry) = entry.filter(|entry| !entry.diagnostics.is_empty()) else {
+        return (String::new(), Color::NONE);
+    };
+
+    let mut lines: Vec<String> = entry
+        .diagnostics
+        .iter()
+        .take(SHOWN_DIAGNOSTICS)
+        .map(Diagnostic::to_string)
+        .collect();
+    if entry.diagnostics.len() > SHOWN_DIAGNOSTICS {
+        lines.push(format!(
+            "...and {} more (E: open in editor)",
+            entry.diagnostics.len() - SHOWN_DIAGNOSTICS
+        ));
+    }
+
+    let worst = entry.diagnostics.iter().map(|d| d.severity).max();
+    (lines.join("\n"), worst.map_or(Color::NONE, severity_color))
+}
+
+fn autoplay_label(config: &AutoplayConfig) -> String {
+    format!("Autoplay: {}", if config.enabled { "ON" } else { "OFF" })
+}
+
+/// Scan for .zuchsya files in the beatmaps folder and its set folders
+///
+/// Set archives (.zsz) found in the beatmaps folder are imported first.
 fn scan_beatmaps(mut beatmap_list: ResMut<BeatmapList>, mut selected: ResMut<SelectedBeatmap>) {
     beatmap_list.maps.clear();
     selected.index = 0;
 
-    // Try multiple possible locations
+    // Try the working directory and the executable's folder, scanning each folder once
     let possible_paths = [
-        PathBuf::from("beatmaps"),
-        PathBuf::from("./beatmaps"),
+        Some(PathBuf::from("beatmaps")),
         std::env::current_exe()
             .ok()
-            .and_then(|p| p.parent().map(|p| p.join("beatmaps")))
-            .unwrap_or_default(),
+            .and_then(|p| p.parent().map(|p| p.join("beatmaps"))),
     ];
+    let mut base_paths: Vec<PathBuf> = Vec::new();
+    for path in possible_paths.into_iter().flatten() {
+        if let Ok(path) = path.canonicalize()
+            && !base_paths.contains(&path)
+        {
+            base_paths.push(path);
+        }
+    }
+
+    for base_path in &base_paths {
+        import_set_archives(base_path);
 
-    for base_path in &possible_paths {
         if let Ok(entries) = std::fs::read_dir(base_path) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.extension().is_some_and(|ext| ext == "zuchsya") {
-                    if let Ok(map) = ZuchsyaMap::load(&path) {
-                        beatmap_list.maps.push(BeatmapEntry {
-                            path: path.clone(),
-                            title: map.metadata.title,
-                            artist: map.metadata.artist,
-                            difficulty: map.metadata.difficulty_name,
-                        });
+                if path.is_dir() {
+                    if let Ok(set_entries) = std::fs::read_dir(&path) {
+                        for set_entry in set_entries.flatten() {
+                            add_beatmap(&mut beatmap_list, set_entry.path());
+                        }
                     }
+                } else {
+                    add_beatmap(&mut beatmap_list, path);
                 }
             }
         }
     }
 
-    // Sort by title
-    beatmap_list.maps.sort_by(|a, b| a.title.cmp(&b.title));
+    // Sort by title, keeping difficulties of a set together
+    beatmap_list.maps.sort_by(|a, b| {
+        a.title
+            .cmp(&b.title)
+            .then_with(|| a.set_id.cmp(&b.set_id))
+            .then_with(|| a.difficulty.cmp(&b.difficulty))
+    });
+}
+
+/// Add a .zuchsya file to the list if it parses, with its validation problems
+fn add_beatmap(beatmap_list: &mut BeatmapList, path: PathBuf) {
+    if path.extension().is_some_and(|ext| ext == "zuchsya")
+        && let Ok(map) = ZuchsyaMap::load_unvalidated(&path)
+    {
+        let mut diagnostics = map.diagnostics();
+        if let Some(folder) = path.parent() {
+            diagnostics.extend(map.asset_diagnostics(folder));
+        }
+
+        let mut bookmarks: Vec<f64> = map
+            .editor
+            .iter()
+            .flat_map(|editor| editor.bookmarks.iter().map(|&time| time as f64))
+            .collect();
+        bookmarks.sort_by(f64::total_cmp);
+
+        beatmap_list.maps.push(BeatmapEntry {
+            path,
+            bookmarks,
+            duration: map.duration(),
+            star_rating: map.star_rating(),
+            hash: map.content_hash(),
+            patterns: map.analyze_patterns(),
+            set_id: map.set_id(),
+            title: map.metadata.title,
+            artist: map.metadata.artist,
+            difficulty: map.metadata.difficulty_name,
+            diagnostics,
+        });
+    }
+}
+
+/// Extract set archives into their own folders, removing the imported archives
+///
+/// Archives that fail to import are renamed to `.zsz.failed`.
+fn import_set_archives(base_path: &Path) {
+    let Ok(entries) = std::fs::read_dir(base_path) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_archive = path
+            .extension()
+            .is_some_and(|ext| ext == SET_ARCHIVE_EXTENSION);
+        if !is_archive {
+            continue;
+        }
+
+        match import_set(&path, base_path) {
+            Ok(folder) => {
+                info!("Imported {} into {}", path.display(), folder.display());
+                if let Err(err) = std::fs::remove_file(&path) {
+                    warn!(
+                        "Failed to remove imported archive {}: {}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
+            Err(err) => {
+                warn!("Failed to import {}: {}", path.display(), err);
+                let failed = path.with_extension(format!(
+                    "{}.{}",
+                    SET_ARCHIVE_EXTENSION, FAILED_ARCHIVE_SUFFIX
+                ));
+                if let Err(err) = std::fs::rename(&path, &failed) {
+                    warn!(
+                        "Failed to rename failed archive {}: {}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
+        }
+    }
 }
 
 fn setup_song_select(
     mut commands: Commands,
     beatmap_list: Res<BeatmapList>,
     selected: Res<SelectedBeatmap>,
+    selected_mods: Res<SelectedMods>,
+    music_config: Res<MusicConfig>,
+    practice_config: Res<PracticeConfig>,
+    autoplay_config: Res<AutoplayConfig>,
+    scores: Res<LocalScores>,
 ) {
+    let selected_entry = beatmap_list.maps.get(selected.index);
+
     commands
         .spawn((
             SongSelectScreen,
@@ -165,10 +471,7 @@ fn setup_song_select(
                             ))
                             .with_children(|item| {
                                 item.spawn((
-                                    Text::new(format!(
-                                        "{} - {} [{}]",
-                                        entry.artist, entry.title, entry.difficulty
-                                    )),
+                                    Text::new(entry.label()),
                                     TextFont {
                                         font_size: 18.0,
                                         ..default()
@@ -184,9 +487,64 @@ fn setup_song_select(
                     }
                 });
 
+            // Patterns, leaderboard and validation problems of the selected beatmap
+            for detail in [
+                SelectionDetail::Patterns,
+                SelectionDetail::Leaderboard,
+                SelectionDetail::Diagnostics,
+            ] {
+                let (text, color) = selection_detail(detail, selected_entry, &scores);
+                parent.spawn((
+                    detail,
+                    Text::new(text),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                    Node {
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                ));
+            }
+
+            // Gameplay options
+            parent.spawn((
+                ModsText,
+                Text::new(mods_label(&selected_mods.0, &music_config)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                Node {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                AutoplayText,
+                Text::new(autoplay_label(&autoplay_config)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+            parent.spawn((
+                PracticeText,
+                Text::new(practice_label(&practice_config, selected_entry)),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+
             // Instructions
             parent.spawn((
-                Text::new("UP/DOWN: Select | ENTER: Play | ESC: Back"),
+                Text::new("UP/DOWN: Select | ENTER: Play | F2: Autoplay | F3: Export Set | E: Edit | ESC: Back\nMods - F1: NF | Z: EZ | X: HR | Q: HT | W: DT | S: SD | P: PF | M: MR | R: RD | [/]: Rate | T: Keep Pitch\nPractice - ,/.: Start | B: Next Bookmark | L: Loop Length | BACKSPACE: Clear"),
                 TextFont {
                     font_size: 18.0,
                     ..default()
@@ -254,16 +612,15 @@ fn handle_input(
             }
         }
 
-        if keyboard.just_pressed(KeyCode::Enter) {
-            if let Some(entry) = beatmap_list.maps.get(selected.index) {
-                // Load the selected beatmap
-                if let Ok(map) = ZuchsyaMap::load(&entry.path) {
-                    commands.insert_resource(CurrentHitObjects {
-                        objects: map.hit_objects,
-                    });
-                    next_state.set(GameState::Playing);
-                }
-            }
+        if keyboard.just_pressed(KeyCode::Enter)
+            && let Some(entry) = beatmap_list.maps.get(selected.index)
+            && !entry.has_errors()
+            // Load the selected beatmap
+            && let Ok(beatmap) = CurrentBeatmap::load(&entry.path)
+        {
+            commands.insert_resource(beatmap);
+            commands.remove_resource::<ReplayPlayback>();
+            next_state.set(GameState::Playing);
         }
     }
 
@@ -272,6 +629,175 @@ fn handle_input(
     }
 }
 
+fn toggle_mods(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut selected_mods: ResMut<SelectedMods>,
+    mut music_config: ResMut<MusicConfig>,
+    mut texts: Query<&mut Text, With<ModsText>>,
+) {
+    let mut changed = false;
+    for (key, m) in MOD_KEYS {
+        if keyboard.just_pressed(key) {
+            selected_mods.0.toggle(m);
+            changed = true;
+        }
+    }
+
+    // Fine rate control for practice
+    let rate = selected_mods.0.rate();
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        selected_mods.0.set_rate(rate - RATE_STEP);
+        changed = true;
+    }
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        selected_mods.0.set_rate(rate + RATE_STEP);
+        changed = true;
+    }
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        music_config.preserve_pitch = !music_config.preserve_pitch;
+        changed = true;
+    }
+
+    if changed {
+        for mut text in texts.iter_mut() {
+            **text = mods_label(&selected_mods.0, &music_config);
+        }
+    }
+}
+
+fn toggle_autoplay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut autoplay_config: ResMut<AutoplayConfig>,
+    mut texts: Query<&mut Text, With<AutoplayText>>,
+) {
+    if keyboard.just_pressed(KeyCode::F2) {
+        autoplay_config.enabled = !autoplay_config.enabled;
+        for mut text in texts.iter_mut() {
+            **text = autoplay_label(&autoplay_config);
+        }
+    }
+}
+
+/// Pick the practice start and loop for the selected beatmap
+fn adjust_practice(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    beatmap_list: Res<BeatmapList>,
+    selected: Res<SelectedBeatmap>,
+    mut config: ResMut<PracticeConfig>,
+    mut texts: Query<&mut Text, With<PracticeText>>,
+) {
+    let entry = beatmap_list.maps.get(selected.index);
+    if let Some(entry) = entry {
+        let hash = &entry.hash;
+        let active = config.is_active_for(hash);
+        let start = if active { config.start() } else { 0.0 };
+        let loop_length = config
+            .loop_end_ms
+            .filter(|_| active)
+            .map(|end| end - start);
+
+        if keyboard.just_pressed(KeyCode::Comma) {
+            config.set_start(hash, start - PRACTICE_STEP_MS);
+        }
+        if keyboard.just_pressed(KeyCode::Period) {
+            config.set_start(hash, (start + PRACTICE_STEP_MS).min(entry.duration));
+        }
+        if keyboard.just_pressed(KeyCode::KeyB) {
+            // Next bookmark after the current start, back to the beginning after the last
+            let next = entry.bookmarks.iter().find(|&&time| time > start);
+            config.set_start(hash, next.copied().unwrap_or(0.0));
+        }
+        if keyboard.just_pressed(KeyCode::KeyL) {
+            let next_length = match loop_length {
+                None => PRACTICE_LOOP_LENGTHS_MS.first().copied(),
+                Some(length) => PRACTICE_LOOP_LENGTHS_MS
+                    .iter()
+                    .find(|&&l| l > length)
+                    .copied(),
+            };
+            config.set_loop_end(hash, next_length.map(|length| start + length));
+        }
+        if keyboard.just_pressed(KeyCode::Backspace) {
+            *config = PracticeConfig::default();
+        }
+    }
+
+    if config.is_changed() || selected.is_changed() {
+        for mut text in texts.iter_mut() {
+            **text = practice_label(&config, entry);
+        }
+    }
+}
+
+/// Export the selected beatmap's set folder as an archive
+fn export_selected_set(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    beatmap_list: Res<BeatmapList>,
+    selected: Res<SelectedBeatmap>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+    let Some(folder) = beatmap_list
+        .maps
+        .get(selected.index)
+        .and_then(|entry| entry.path.parent())
+    else {
+        return;
+    };
+
+    let name = folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "beatmaps".to_string());
+    let archive = PathBuf::from(EXPORT_FOLDER).join(format!("{}.{}", name, SET_ARCHIVE_EXTENSION));
+
+    match export_set(folder, &archive) {
+        Ok(()) => info!("Exported {} to {}", folder.display(), archive.display()),
+        Err(err) => warn!("Failed to export {}: {}", folder.display(), err),
+    }
+}
+
+/// Open the selected beatmap in the editor
+fn open_editor(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    beatmap_list: Res<BeatmapList>,
+    selected: Res<SelectedBeatmap>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    if keyboard.just_pressed(KeyCode::KeyE)
+        && let Some(entry) = beatmap_list.maps.get(selected.index)
+    {
+        match EditorBeatmap::load(&entry.path) {
+            Ok(beatmap) => {
+                commands.insert_resource(beatmap);
+                next_state.set(GameState::Editor);
+            }
+            Err(err) => warn!("Failed to open {}: {}", entry.path.display(), err),
+        }
+    }
+}
+
+/// Show the details of the newly selected beatmap
+fn update_selection_details(
+    beatmap_list: Res<BeatmapList>,
+    selected: Res<SelectedBeatmap>,
+    scores: Res<LocalScores>,
+    mut texts: Query<(&SelectionDetail, &mut Text, &mut TextColor)>,
+) {
+    if !selected.is_changed() {
+        return;
+    }
+
+    let entry = beatmap_list.maps.get(selected.index);
+    for (detail, mut text, mut text_color) in texts.iter_mut() {
+        let (detail_text, color) = selection_detail(*detail, entry, &scores);
+        **text = detail_text;
+        *text_color = TextColor(color);
+    }
+}
+
 fn cleanup_song_select(mut commands: Commands, query: Query<Entity, With<SongSelectScreen>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();